@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+
+// Cycle accounting shared by the Guest and the benchmark harness.
+//
+// When the Host asks for them (the first input of the scoring Guest), the Guest writes a
+// marker line to stdout at the end of each phase with the cycle count reached so far. Only
+// the benchmark asks, so production proofs do not spend cycles on them. The Host captures
+// stdout and charges the cycles between two markers to the phase named by the later one, so
+// phases read like the Guest log messages.
+
+pub const PHASE_MARKER: &str = "zero-raf-phase";
+
+// Version of the `BenchmarkReport` JSON layout
+pub const BENCHMARK_REPORT_VERSION: u32 = 1;
+
+/// Marker line written by the Guest when `phase` completes at `cycle`
+pub fn phase_marker(phase: &str, cycle: u64) -> String {
+    format!("{}\t{}\t{}\n", PHASE_MARKER, cycle, phase)
+}
+
+/// Cycles charged to a single Guest phase
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseCycles {
+    pub phase: String,
+    pub cycles: u64,
+}
+
+/*
+    Turns the marker lines of a Guest run into the cycles spent in each phase. Any
+    cycles after the last marker (up to `total_cycles`) are reported as "remaining".
+    Lines that are not markers are ignored.
+*/
+pub fn phase_cycles(stdout: &str, total_cycles: u64) -> Vec<PhaseCycles> {
+
+    let mut phases = vec![];
+    let mut previous = 0;
+    for line in stdout.lines() {
+        let mut fields = line.splitn(3, '\t');
+        if fields.next() != Some(PHASE_MARKER) {
+            continue;
+        }
+        let cycle = match fields.next().and_then(|x| x.parse::<u64>().ok()) {
+            Some(cycle) => cycle,
+            None => continue,
+        };
+        let phase = fields.next().unwrap_or("").to_string();
+        phases.push(PhaseCycles { phase, cycles: cycle.saturating_sub(previous) });
+        previous = previous.max(cycle);
+    }
+    if total_cycles > previous {
+        phases.push(PhaseCycles { phase: "remaining".to_string(), cycles: total_cycles - previous });
+    }
+    return phases;
+}
+
+/// Adds the phases of one run to a running total, keeping the order phases were first seen
+pub fn accumulate_phases(total: &mut Vec<PhaseCycles>, run: &[PhaseCycles]) {
+    for phase in run {
+        match total.iter_mut().find(|x| x.phase == phase.phase) {
+            Some(existing) => existing.cycles += phase.cycles,
+            None => total.push(phase.clone()),
+        }
+    }
+}
+
+/// Measurements for one roster shape
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkCase {
+    // Label of the case, e.g. "members=10,dx=5"
+    pub name: String,
+
+    pub members: usize,
+    pub dx_per_member: usize,
+
+    // Summed over every member of the roster
+    pub segments: usize,
+    pub total_cycles: u64,
+    pub phases: Vec<PhaseCycles>,
+    pub execute_ms: u64,
+
+    // None when the case was only executed
+    pub prove_ms: Option<u64>,
+}
+
+impl BenchmarkCase {
+    pub fn cycles_per_member(&self) -> u64 {
+        if self.members == 0 {
+            return 0;
+        }
+        return self.total_cycles / self.members as u64;
+    }
+}
+
+/// Machine-readable output of the benchmark binary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    pub version: u32,
+
+    // Hex image id of the Guest that was measured
+    pub image_id: String,
+
+    // Seed of the synthetic rosters, so a run can be reproduced
+    pub seed: u64,
+
+    pub cases: Vec<BenchmarkCase>,
+}
+
+/*
+    Compares the cycles per member of every case found in both reports and describes
+    each one that grew by more than `tolerance` (0.05 = 5%) over the baseline.
+*/
+pub fn find_cycle_regressions(baseline: &BenchmarkReport, current: &BenchmarkReport, tolerance: f64) -> Vec<String> {
+
+    let mut regressions = vec![];
+    for case in &current.cases {
+        let before = match baseline.cases.iter().find(|x| x.name == case.name) {
+            Some(before) => before.cycles_per_member(),
+            None => continue,
+        };
+        let after = case.cycles_per_member();
+        if after as f64 > before as f64 * (1.0 + tolerance) {
+            regressions.push(format!("{}: {} -> {} cycles per member", case.name, before, after));
+        }
+    }
+    return regressions;
+}
+
+#[cfg(test)]
+fn _case(name: &str, members: usize, total_cycles: u64) -> BenchmarkCase {
+    BenchmarkCase {
+        name: name.to_string(),
+        members,
+        dx_per_member: 5,
+        segments: members,
+        total_cycles,
+        phases: vec![],
+        execute_ms: 1,
+        prove_ms: None,
+    }
+}
+
+#[test]
+fn can_split_guest_stdout_into_phase_cycles() {
+    let stdout = phase_marker("Retrieved public inputs", 1000)
+        + "unrelated output\n"
+        + &phase_marker("Applied hierarchy to HCC list", 1500)
+        + &phase_marker("Committed journal", 4000);
+    let phases = phase_cycles(&stdout, 4200);
+    let cycles: Vec<(&str, u64)> = phases.iter().map(|x| (x.phase.as_str(), x.cycles)).collect();
+    assert_eq!(cycles, vec![
+        ("Retrieved public inputs", 1000),
+        ("Applied hierarchy to HCC list", 500),
+        ("Committed journal", 2500),
+        ("remaining", 200),
+    ]);
+
+    let mut total = vec![];
+    accumulate_phases(&mut total, &phases);
+    accumulate_phases(&mut total, &phases[1..2]);
+    assert_eq!(total[1].cycles, 1000);
+    assert_eq!(total.len(), 4);
+}
+
+#[test]
+fn can_find_cycle_regressions() {
+    let report = |cases| BenchmarkReport { version: BENCHMARK_REPORT_VERSION, image_id: "00".to_string(), seed: 7, cases };
+    let baseline = report(vec![_case("members=1,dx=5", 1, 1000), _case("members=10,dx=5", 10, 10_000)]);
+    let current = report(vec![
+        _case("members=1,dx=5", 1, 1040),
+        _case("members=10,dx=5", 10, 12_000),
+        _case("members=100,dx=5", 100, 500_000),
+    ]);
+    let regressions = find_cycle_regressions(&baseline, &current, 0.05);
+    assert_eq!(regressions, vec!["members=10,dx=5: 1000 -> 1200 cycles per member".to_string()]);
+}
@@ -0,0 +1,148 @@
+use std::fmt;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Scheduling of roster chunks over a pool of Host workers.
+//
+// Proving one member at a time on a CPU leaves most cores idle, and a single session over a
+// whole roster runs for days. The Host splits the roster into chunks of consecutive members
+// with `chunk_ranges` and `run_chunks` hands the chunks to a fixed number of worker threads,
+// reporting a `ChunkProgress` each time a chunk completes. The work done per chunk (executing
+// and proving the members) is up to the caller, so the scheduling is tested without a zkVM.
+
+/// Consecutive member ranges of at most `chunk_size` members covering `members`
+pub fn chunk_ranges(members: usize, chunk_size: usize) -> Vec<Range<usize>> {
+    let chunk_size = chunk_size.max(1);
+    (0..members)
+        .step_by(chunk_size)
+        .map(|start| start..(start + chunk_size).min(members))
+        .collect()
+}
+
+/// Reported each time a chunk completes
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkProgress {
+    // Index of the chunk that just completed
+    pub chunk: usize,
+
+    pub chunks_done: usize,
+    pub chunks: usize,
+    pub members_done: usize,
+    pub members: usize,
+
+    // Time since `run_chunks` started
+    pub elapsed: Duration,
+}
+
+impl ChunkProgress {
+    /// Time left at the rate members were completed so far
+    pub fn remaining(&self) -> Duration {
+        if self.members_done == 0 {
+            return Duration::ZERO;
+        }
+        let left = (self.members - self.members_done) as f64;
+        Duration::from_secs_f64(self.elapsed.as_secs_f64() * left / self.members_done as f64)
+    }
+}
+
+impl fmt::Display for ChunkProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "chunk {} done, {}/{} chunks, {}/{} members, {:.0}s elapsed, ~{:.0}s remaining",
+               self.chunk, self.chunks_done, self.chunks, self.members_done, self.members,
+               self.elapsed.as_secs_f64(), self.remaining().as_secs_f64())
+    }
+}
+
+/*
+    Runs `work` on every chunk with up to `workers` threads and returns the results in chunk
+    order. `progress` is called once per completed chunk, one call at a time. After the
+    first failure no new chunk is started and the error of the lowest failed chunk is
+    returned.
+*/
+pub fn run_chunks<T, E, W, P>(chunks: &[Range<usize>], workers: usize, work: W, progress: P) -> Result<Vec<T>, E>
+where
+    T: Send,
+    E: Send,
+    W: Fn(usize, Range<usize>) -> Result<T, E> + Sync,
+    P: Fn(&ChunkProgress) + Sync,
+{
+    let started = Instant::now();
+    let members: usize = chunks.iter().map(|x| x.len()).sum();
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+
+    // Chunks and members done, locked while progress is reported so reports stay in order
+    let done = Mutex::new((0usize, 0usize));
+    let results: Mutex<Vec<Option<Result<T, E>>>> = Mutex::new(chunks.iter().map(|_| None).collect());
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers.max(1).min(chunks.len()) {
+            scope.spawn(|| loop {
+                let chunk = next.fetch_add(1, Ordering::SeqCst);
+                if chunk >= chunks.len() || failed.load(Ordering::SeqCst) {
+                    break;
+                }
+                let result = work(chunk, chunks[chunk].clone());
+                if result.is_err() {
+                    failed.store(true, Ordering::SeqCst);
+                } else {
+                    let mut done = done.lock().unwrap();
+                    done.0 += 1;
+                    done.1 += chunks[chunk].len();
+                    progress(&ChunkProgress {
+                        chunk,
+                        chunks_done: done.0,
+                        chunks: chunks.len(),
+                        members_done: done.1,
+                        members,
+                        elapsed: started.elapsed(),
+                    });
+                }
+                results.lock().unwrap()[chunk] = Some(result);
+            });
+        }
+    });
+
+    // Chunks never started after a failure are skipped, the error is still collected
+    results.into_inner().unwrap().into_iter().flatten().collect()
+}
+
+#[test]
+fn can_split_rosters_into_chunks() {
+    assert_eq!(chunk_ranges(10, 4), vec![0..4, 4..8, 8..10]);
+    assert_eq!(chunk_ranges(8, 4), vec![0..4, 4..8]);
+    assert_eq!(chunk_ranges(3, 0), vec![0..1, 1..2, 2..3]);
+    assert!(chunk_ranges(0, 4).is_empty());
+}
+
+#[test]
+fn can_run_chunks_on_several_workers() {
+    let chunks = chunk_ranges(25, 3);
+    let reports = Mutex::new(vec![]);
+    let sums = run_chunks(&chunks, 4, |_, range| Ok::<usize, String>(range.sum()), |x| reports.lock().unwrap().push(x.clone())).unwrap();
+    assert_eq!(sums, chunks.iter().map(|x| x.clone().sum()).collect::<Vec<usize>>());
+
+    // Counts only grow and the last report covers the whole roster
+    let reports = reports.into_inner().unwrap();
+    assert_eq!(reports.len(), chunks.len());
+    assert!(reports.windows(2).all(|x| x[0].chunks_done < x[1].chunks_done && x[0].members_done < x[1].members_done));
+    assert_eq!((reports[8].chunks_done, reports[8].members_done, reports[8].members), (9, 25, 25));
+    assert_eq!(reports[8].remaining(), Duration::ZERO);
+
+    let empty = run_chunks(&[], 4, |_, _| Ok::<(), String>(()), |_| panic!("nothing to report"));
+    assert_eq!(empty, Ok(vec![]));
+}
+
+#[test]
+fn stops_at_the_first_failed_chunk() {
+    let chunks = chunk_ranges(40, 2);
+    let started = AtomicUsize::new(0);
+    let result = run_chunks(&chunks, 1, |chunk, _| {
+        started.fetch_add(1, Ordering::SeqCst);
+        if chunk == 3 { Err(format!("chunk {} failed", chunk)) } else { Ok(chunk) }
+    }, |_| {});
+    assert_eq!(result, Err("chunk 3 failed".to_string()));
+    assert_eq!(started.load(Ordering::SeqCst), 4);
+}
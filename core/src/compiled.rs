@@ -0,0 +1,655 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use crate::dx_table::DxTableCommitment;
+use crate::hierarchy::set0_statements;
+use crate::interactions::InteractionDefinitions;
+use crate::merkle::{Hash, MerkleHasher};
+use crate::validation::find_hierarchy_cycles;
+use crate::PublicRAFInputs;
+
+// Compact, integer-indexed form of `PublicRAFInputs`.
+//
+// The Host compiles the CMS tables once into a flat `Vec<u32>` which the Guest reads
+// as a single word slice and queries in place through `CompiledTablesView`. Names are
+// kept in sorted string tables so lookups are binary searches over borrowed bytes, and
+// HCCs, regression variables and models are referred to by their index in those tables.
+// The dx_to_cc mapping is not part of the compiled tables, it is committed separately
+// (see `dx_table`) so the Guest only receives the rows a patient needs.
+//
+// Layout (every entry is one u32 word, strings are packed little-endian):
+//   MAGIC VERSION norm_factor
+//   string table: hccs
+//   string table: variables
+//   string table: models
+//   coefficients        models.len() * variables.len() f32 bits, NaN when missing
+//   hierarchy           count, superior HCC numbers, count + 1 offsets, then inferior HCC numbers
+//   scores              string table: score variables, string table: coefficient prefixes,
+//                       count, regression list of each score, then count, count + 1 offsets
+//                       and the variable ids of each regression list
+//   payment hccs        count, sorted HCC ids of the model payment HCCs (&HCClist)
+// where a string table is `count, count + 1 byte offsets, word count, bytes`. The score
+// tables keep the %SCOREMAC order and are read by index only, so they are not sorted, and
+// scores sharing a %LET list (e.g. CNA, CFA and CPA on &COMM_REGA) share its variable ids.
+
+pub const COMPILED_TABLES_MAGIC: u32 = 0x5a52_4146; // "ZRAF"
+pub const COMPILED_TABLES_VERSION: u32 = 5;
+
+/// Errors raised while compiling the public tables or reading them back
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompiledTablesError {
+    // A table references an HCC that is not one of the model HCCs
+    UnknownHcc(String),
+
+    // A coefficient name does not have the `PREFIX_VARIABLE` shape
+    InvalidCoefficientName(String),
+
+    // The hierarchies contain a cycle, listed from and back to the same HCC
+    CyclicHierarchy(Vec<String>),
+
+    // The word buffer does not start with the expected magic and version
+    UnsupportedFormat { magic: u32, version: u32 },
+
+    // The word buffer ended before the section being read
+    Truncated { section: &'static str },
+
+    // A section of the word buffer is inconsistent with itself
+    Corrupt { section: &'static str },
+}
+
+impl fmt::Display for CompiledTablesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompiledTablesError::UnknownHcc(hcc) => write!(f, "{} is not a model HCC", hcc),
+            CompiledTablesError::InvalidCoefficientName(name) => write!(f, "coefficient {:?} has no model prefix", name),
+            CompiledTablesError::CyclicHierarchy(cycle) => write!(f, "hierarchies contain a cycle: {}", cycle.join(" -> ")),
+            CompiledTablesError::UnsupportedFormat { magic, version } => {
+                write!(f, "unsupported compiled tables (magic {:#x}, version {})", magic, version)
+            }
+            CompiledTablesError::Truncated { section } => write!(f, "compiled tables truncated in {}", section),
+            CompiledTablesError::Corrupt { section } => write!(f, "compiled tables corrupt in {}", section),
+        }
+    }
+}
+
+impl std::error::Error for CompiledTablesError {}
+
+/// Owned, integer-indexed copy of the public tables, built by the Host
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledRAFTables {
+    // Model HCC names sorted by name; the position of a name is its HCC id
+    pub hccs: Vec<String>,
+
+    // Regression variable names without their model prefix, sorted; position is the variable id
+    pub variables: Vec<String>,
+
+    // Coefficient prefixes (CNA, CND, ... SNPNE) sorted; position is the model id
+    pub models: Vec<String>,
+
+    // Coefficient of variable `v` in model `m` at `m * variables.len() + v`, NaN when not published
+    pub coefficients: Vec<f32>,
+
+    // %SET0 statements in V28115H1 order: superior HCC number and the HCC numbers it zeroes
+    pub hierarchies: Vec<(u32, Vec<u32>)>,
+
+    // %SCOREMAC calls in SAS order: score variable, coefficient prefix and regression list index
+    pub scores: Vec<(String, String, u32)>,
+
+    // Variable ids of each distinct regression list. Variables without a coefficient in
+    // any model cannot change a score and are left out.
+    pub regression_lists: Vec<Vec<u32>>,
+
+    // HCC ids of the payment HCCs counted into D1 .. D10P, sorted. Payment HCCs without a
+    // label have no id and are left out, `PublicRAFInputs::validate` reports them.
+    pub payment_hccs: Vec<u32>,
+
+    // Normalization factor
+    pub norm_factor: f32,
+}
+
+impl CompiledRAFTables {
+
+    /// Converts the string keyed CMS tables into their integer-indexed form
+    pub fn compile(public_inputs: &PublicRAFInputs) -> Result<Self, CompiledTablesError> {
+
+        let hccs: Vec<String> = public_inputs.model_hccs().iter().map(|x| x.to_string()).collect();
+        let hcc_id = |hcc: &String| -> Result<u32, CompiledTablesError> {
+            match hccs.binary_search(hcc) {
+                Ok(id) => Ok(id as u32),
+                Err(_) => Err(CompiledTablesError::UnknownHcc(hcc.clone())),
+            }
+        };
+
+        // Split every coefficient name into its model prefix and regression variable
+        let mut split = BTreeMap::<(&str, &str), f32>::new();
+        let mut models = BTreeSet::<&str>::new();
+        let mut variables = BTreeSet::<&str>::new();
+        for (name, coefficient) in &public_inputs.hcc_coefficients {
+            match name.split_once('_') {
+                Some((model, variable)) if !model.is_empty() && !variable.is_empty() => {
+                    models.insert(model);
+                    variables.insert(variable);
+                    split.insert((model, variable), *coefficient);
+                }
+                _ => return Err(CompiledTablesError::InvalidCoefficientName(name.clone())),
+            }
+        }
+        let models: Vec<String> = models.into_iter().map(|x| x.to_string()).collect();
+        let variables: Vec<String> = variables.into_iter().map(|x| x.to_string()).collect();
+        let mut coefficients = vec![f32::NAN; models.len() * variables.len()];
+        for ((model, variable), coefficient) in split {
+            let m = models.binary_search_by(|x| x.as_str().cmp(model)).unwrap();
+            let v = variables.binary_search_by(|x| x.as_str().cmp(variable)).unwrap();
+            coefficients[m * variables.len() + v] = coefficient;
+        }
+
+        // Hierarchies may only refer to numbered model HCCs and must not loop back on themselves
+        for hcc in public_inputs.hcc_hierarchies.iter().flat_map(|(k, v)| std::iter::once(k).chain(v)) {
+            hcc_id(hcc)?;
+        }
+        if let Some(cycle) = find_hierarchy_cycles(&public_inputs.hcc_hierarchies).into_iter().next() {
+            return Err(CompiledTablesError::CyclicHierarchy(cycle));
+        }
+        let hierarchies = set0_statements(&public_inputs.hcc_hierarchies).map_err(CompiledTablesError::UnknownHcc)?;
+
+        let mut scores = vec![];
+        let mut regression_lists: Vec<Vec<u32>> = vec![];
+        for score in &public_inputs.model.scores {
+            let ids: Vec<u32> = score.variables.iter().filter_map(|x| variables.binary_search(x).ok()).map(|x| x as u32).collect();
+            let list = match regression_lists.iter().position(|x| *x == ids) {
+                Some(list) => list,
+                None => {
+                    regression_lists.push(ids);
+                    regression_lists.len() - 1
+                }
+            };
+            scores.push((score.score.clone(), score.prefix.clone(), list as u32));
+        }
+
+        let mut payment_hccs: Vec<u32> = public_inputs.model.payment_hccs().iter().filter_map(|x| hcc_id(x).ok()).collect();
+        payment_hccs.sort();
+        payment_hccs.dedup();
+
+        Ok(CompiledRAFTables {
+            hccs,
+            variables,
+            models,
+            coefficients,
+            hierarchies,
+            scores,
+            regression_lists,
+            payment_hccs,
+            norm_factor: public_inputs.norm_factor,
+        })
+    }
+
+    /// Encodes the tables into the flat word layout read by `CompiledTablesView`
+    pub fn to_words(&self) -> Vec<u32> {
+
+        let mut words = vec![COMPILED_TABLES_MAGIC, COMPILED_TABLES_VERSION, self.norm_factor.to_bits()];
+        _push_string_table(&mut words, &self.hccs);
+        _push_string_table(&mut words, &self.variables);
+        _push_string_table(&mut words, &self.models);
+
+        words.extend(self.coefficients.iter().map(|c| c.to_bits()));
+
+        words.push(self.hierarchies.len() as u32);
+        words.extend(self.hierarchies.iter().map(|x| x.0));
+        let inferiors: Vec<Vec<u32>> = self.hierarchies.iter().map(|x| x.1.clone()).collect();
+        _push_id_lists(&mut words, &inferiors);
+
+        let score_names: Vec<String> = self.scores.iter().map(|x| x.0.clone()).collect();
+        let score_prefixes: Vec<String> = self.scores.iter().map(|x| x.1.clone()).collect();
+        _push_string_table(&mut words, &score_names);
+        _push_string_table(&mut words, &score_prefixes);
+        words.push(self.scores.len() as u32);
+        words.extend(self.scores.iter().map(|x| x.2));
+        _push_id_lists(&mut words, &self.regression_lists);
+
+        words.push(self.payment_hccs.len() as u32);
+        words.extend(self.payment_hccs.iter());
+
+        words
+    }
+}
+
+fn _push_string_table(words: &mut Vec<u32>, strings: &[String]) {
+    words.push(strings.len() as u32);
+    let mut offset = 0;
+    words.push(0);
+    for s in strings {
+        offset += s.len() as u32;
+        words.push(offset);
+    }
+    let bytes: Vec<u8> = strings.iter().flat_map(|s| s.bytes()).collect();
+    words.push(((bytes.len() + 3) / 4) as u32);
+    for chunk in bytes.chunks(4) {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        words.push(u32::from_le_bytes(word));
+    }
+}
+
+fn _push_id_lists(words: &mut Vec<u32>, lists: &[Vec<u32>]) {
+    words.push(lists.len() as u32);
+    let mut offset = 0;
+    words.push(0);
+    for list in lists {
+        offset += list.len() as u32;
+        words.push(offset);
+    }
+    for list in lists {
+        words.extend(list.iter());
+    }
+}
+
+// Reads consecutive sections out of the word buffer
+struct Cursor<'a> {
+    words: &'a [u32],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize, section: &'static str) -> Result<&'a [u32], CompiledTablesError> {
+        if self.words.len() - self.pos < n {
+            return Err(CompiledTablesError::Truncated { section });
+        }
+        let slice = &self.words[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(slice)
+    }
+
+    fn word(&mut self, section: &'static str) -> Result<u32, CompiledTablesError> {
+        Ok(self.take(1, section)?[0])
+    }
+
+    // A count word, rejected when `count + 1` would wrap on the 32-bit Guest
+    fn count(&mut self, section: &'static str) -> Result<usize, CompiledTablesError> {
+        let count = self.word(section)? as usize;
+        count.checked_add(1).ok_or(CompiledTablesError::Corrupt { section })?;
+        Ok(count)
+    }
+
+    fn string_table(&mut self, section: &'static str) -> Result<StringTable<'a>, CompiledTablesError> {
+        let count = self.count(section)?;
+        let offsets = self.take(count + 1, section)?;
+        let byte_words = self.word(section)? as usize;
+        let bytes: &'a [u8] = bytemuck::cast_slice(self.take(byte_words, section)?);
+        if !_offsets_are_valid(offsets, bytes.len()) {
+            return Err(CompiledTablesError::Corrupt { section });
+        }
+        Ok(StringTable { offsets, bytes })
+    }
+
+    fn id_lists(&mut self, section: &'static str, max_id: usize) -> Result<IdLists<'a>, CompiledTablesError> {
+        let count = self.count(section)?;
+        let offsets = self.take(count + 1, section)?;
+        let total = *offsets.last().unwrap() as usize;
+        let ids = self.take(total, section)?;
+        if !_offsets_are_valid(offsets, total) || ids.iter().any(|id| *id as usize >= max_id) {
+            return Err(CompiledTablesError::Corrupt { section });
+        }
+        Ok(IdLists { offsets, ids })
+    }
+}
+
+fn _offsets_are_valid(offsets: &[u32], len: usize) -> bool {
+    offsets[0] == 0
+        && offsets.windows(2).all(|w| w[0] <= w[1])
+        && *offsets.last().unwrap() as usize <= len
+}
+
+// Sorted strings addressed by index
+#[derive(Clone, Copy)]
+struct StringTable<'a> {
+    offsets: &'a [u32],
+    bytes: &'a [u8],
+}
+
+impl<'a> StringTable<'a> {
+    fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    fn get_bytes(&self, index: usize) -> &'a [u8] {
+        &self.bytes[self.offsets[index] as usize..self.offsets[index + 1] as usize]
+    }
+
+    fn get(&self, index: usize) -> &'a str {
+        std::str::from_utf8(self.get_bytes(index)).unwrap_or("")
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        let (mut lo, mut hi) = (0, self.len());
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            match self.get_bytes(mid).cmp(name.as_bytes()) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => return Some(mid),
+            }
+        }
+        None
+    }
+}
+
+// Lists of ids addressed by index
+#[derive(Clone, Copy)]
+struct IdLists<'a> {
+    offsets: &'a [u32],
+    ids: &'a [u32],
+}
+
+impl<'a> IdLists<'a> {
+    fn get(&self, index: usize) -> &'a [u32] {
+        &self.ids[self.offsets[index] as usize..self.offsets[index + 1] as usize]
+    }
+}
+
+/// Zero-copy reader over the words produced by `CompiledRAFTables::to_words`
+#[derive(Clone, Copy)]
+pub struct CompiledTablesView<'a> {
+    words: &'a [u32],
+    norm_factor: f32,
+    hccs: StringTable<'a>,
+    variables: StringTable<'a>,
+    models: StringTable<'a>,
+    coefficients: &'a [u32],
+    superior_hccs: &'a [u32],
+    hierarchies: IdLists<'a>,
+    score_names: StringTable<'a>,
+    score_prefixes: StringTable<'a>,
+    score_lists: &'a [u32],
+    regression_lists: IdLists<'a>,
+    payment_hccs: &'a [u32],
+}
+
+impl<'a> CompiledTablesView<'a> {
+
+    /// Checks the section sizes of `words` and returns a view borrowing them
+    pub fn new(words: &'a [u32]) -> Result<Self, CompiledTablesError> {
+
+        let mut cursor = Cursor { words, pos: 0 };
+        let header = cursor.take(3, "header")?;
+        if header[0] != COMPILED_TABLES_MAGIC || header[1] != COMPILED_TABLES_VERSION {
+            return Err(CompiledTablesError::UnsupportedFormat { magic: header[0], version: header[1] });
+        }
+        let norm_factor = f32::from_bits(header[2]);
+
+        let hccs = cursor.string_table("hccs")?;
+        let variables = cursor.string_table("variables")?;
+        let models = cursor.string_table("models")?;
+        let coefficient_count = models.len().checked_mul(variables.len()).ok_or(CompiledTablesError::Corrupt { section: "coefficients" })?;
+        let coefficients = cursor.take(coefficient_count, "coefficients")?;
+
+        let statements = cursor.word("hierarchies")? as usize;
+        let superior_hccs = cursor.take(statements, "hierarchies")?;
+        let hierarchies = cursor.id_lists("hierarchies", u32::MAX as usize)?;
+        if hierarchies.offsets.len() - 1 != statements {
+            return Err(CompiledTablesError::Corrupt { section: "hierarchies" });
+        }
+
+        let score_names = cursor.string_table("scores")?;
+        let score_prefixes = cursor.string_table("scores")?;
+        let count = cursor.word("scores")? as usize;
+        let score_lists = cursor.take(count, "scores")?;
+        let regression_lists = cursor.id_lists("scores", variables.len())?;
+        let list_count = (regression_lists.offsets.len() - 1) as u32;
+        if score_prefixes.len() != score_names.len() || count != score_names.len() || score_lists.iter().any(|x| *x >= list_count) {
+            return Err(CompiledTablesError::Corrupt { section: "scores" });
+        }
+
+        let count = cursor.word("payment hccs")? as usize;
+        let payment_hccs = cursor.take(count, "payment hccs")?;
+        if payment_hccs.iter().any(|x| *x as usize >= hccs.len()) || !payment_hccs.windows(2).all(|w| w[0] < w[1]) {
+            return Err(CompiledTablesError::Corrupt { section: "payment hccs" });
+        }
+
+        Ok(CompiledTablesView {
+            words,
+            norm_factor,
+            hccs,
+            variables,
+            models,
+            coefficients,
+            superior_hccs,
+            hierarchies,
+            score_names,
+            score_prefixes,
+            score_lists,
+            regression_lists,
+            payment_hccs,
+        })
+    }
+
+    /// The words the view reads, as passed to `new`
+    pub fn words(&self) -> &'a [u32] {
+        self.words
+    }
+
+    pub fn norm_factor(&self) -> f32 {
+        self.norm_factor
+    }
+
+    pub fn hcc_count(&self) -> usize {
+        self.hccs.len()
+    }
+
+    /// Name of the HCC with the given id, e.g. "HCC17"
+    pub fn hcc_name(&self, id: u32) -> &'a str {
+        self.hccs.get(id as usize)
+    }
+
+    /// Id of a model HCC, None if the name is not a model HCC
+    pub fn hcc_id(&self, name: &str) -> Option<u32> {
+        self.hccs.find(name).map(|id| id as u32)
+    }
+
+    /// %SET0 statements in V28115H1 order, as (superior HCC number, inferior HCC numbers)
+    pub fn hierarchy_statements(&self) -> impl Iterator<Item = (u32, &'a [u32])> + '_ {
+        self.superior_hccs.iter().enumerate().map(|(i, superior)| (*superior, self.hierarchies.get(i)))
+    }
+
+    /// %SCOREMAC calls in SAS order, as (score variable, coefficient prefix, regression variable ids)
+    pub fn scores(&self) -> impl Iterator<Item = (&'a str, &'a str, &'a [u32])> + '_ {
+        (0..self.score_names.len()).map(|i| {
+            (self.score_names.get(i), self.score_prefixes.get(i), self.regression_lists.get(self.score_lists[i] as usize))
+        })
+    }
+
+    /// Whether the HCC is one of the payment HCCs counted into D1 .. D10P
+    pub fn is_payment_hcc(&self, name: &str) -> bool {
+        match self.hcc_id(name) {
+            Some(id) => self.payment_hccs.binary_search(&id).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Id of a regression variable, None if no model publishes a coefficient for it
+    pub fn variable_id(&self, name: &str) -> Option<u32> {
+        self.variables.find(name).map(|id| id as u32)
+    }
+
+    /// Name of the regression variable with the given id, without its model prefix
+    pub fn variable_name(&self, id: u32) -> &'a str {
+        self.variables.get(id as usize)
+    }
+
+    /// Coefficient published for `variable` under the model prefix, e.g. ("CNA", "HCC17")
+    pub fn coefficient(&self, model: &str, variable: &str) -> Option<f32> {
+        let m = self.models.find(model)?;
+        let v = self.variables.find(variable)?;
+        let coefficient = f32::from_bits(self.coefficients[m * self.variables.len() + v]);
+        if coefficient.is_nan() {
+            None
+        } else {
+            Some(coefficient)
+        }
+    }
+}
+
+/*
+    SHA-256 of the compiled words (little endian), committed by every Guest entry point so a
+    verifier can check which public tables a result was computed with.
+*/
+pub fn tables_digest<H: MerkleHasher>(words: &[u32]) -> Hash {
+    let bytes: Vec<u8> = words.iter().flat_map(|x| x.to_le_bytes()).collect();
+    H::sha256(&bytes)
+}
+
+/// SHA-256 of the bincode encoding of the interaction definitions
+pub fn interactions_digest<H: MerkleHasher>(interactions: &InteractionDefinitions) -> Hash {
+    H::sha256(&bincode::serialize(interactions).unwrap())
+}
+
+/*
+    Digest of every public input a journal names: SHA-256 over the tables digest, the
+    dx_to_cc root, its row count (u32, little endian) and the interactions digest. A verifier
+    allows one value per build instead of checking each input on its own.
+*/
+pub fn public_inputs_digest<H: MerkleHasher>(tables_digest: &Hash, dx_table: &DxTableCommitment, interactions: &InteractionDefinitions) -> Hash {
+    let mut bytes = tables_digest.to_vec();
+    bytes.extend_from_slice(&dx_table.root);
+    bytes.extend_from_slice(&dx_table.rows.to_le_bytes());
+    bytes.extend_from_slice(&interactions_digest::<H>(interactions));
+    H::sha256(&bytes)
+}
+
+#[cfg(test)]
+fn _tiny_public_inputs() -> PublicRAFInputs {
+    use crate::interactions::InteractionDefinitions;
+    use crate::model::{ModelDefinition, ScoreDefinition};
+    let mut inputs = PublicRAFInputs {
+        hcc_coefficients: BTreeMap::new(),
+        hcc_hierarchies: BTreeMap::new(),
+        hcc_labels: BTreeMap::new(),
+        dx_to_cc: BTreeMap::new(),
+        interactions: InteractionDefinitions::default(),
+        model: ModelDefinition::default(),
+        norm_factor: 0.9,
+    };
+    for hcc in ["HCC17", "HCC18", "HCC19", "HCC2"] {
+        inputs.hcc_labels.insert(hcc.to_string(), format!("{} label", hcc));
+    }
+    inputs.hcc_coefficients.insert("CNA_HCC17".to_string(), 4.209);
+    inputs.hcc_coefficients.insert("CNA_F65_69".to_string(), 0.33);
+    inputs.hcc_coefficients.insert("INS_HCC18".to_string(), 1.5);
+    inputs.hcc_coefficients.insert("SNPNE_MCAID_ORIGDIS_NEM95_GT".to_string(), 2.573);
+    inputs.hcc_hierarchies.insert("HCC17".to_string(), vec!["HCC18".to_string(), "HCC19".to_string()]);
+    inputs.model.scores.push(ScoreDefinition {
+        score: "SCORE_COMMUNITY_NA".to_string(),
+        prefix: "CNA".to_string(),
+        list: "COMM_REGA".to_string(),
+        variables: vec!["F65_69".to_string(), "HCC17".to_string(), "HCC2".to_string()],
+    });
+    inputs
+}
+
+#[test]
+fn can_read_back_compiled_tables() {
+    let inputs = _tiny_public_inputs();
+    let words = CompiledRAFTables::compile(&inputs).unwrap().to_words();
+    let view = CompiledTablesView::new(&words).unwrap();
+
+    assert_eq!(view.norm_factor(), 0.9);
+    assert_eq!(view.hcc_count(), 4);
+    assert_eq!(view.coefficient("CNA", "HCC17"), Some(4.209));
+    assert_eq!(view.coefficient("SNPNE", "MCAID_ORIGDIS_NEM95_GT"), Some(2.573));
+    assert_eq!(view.coefficient("INS", "HCC17"), None);
+    assert_eq!(view.coefficient("XYZ", "HCC17"), None);
+
+    let statements: Vec<(u32, &[u32])> = view.hierarchy_statements().collect();
+    assert_eq!(statements, vec![(17, &[18, 19][..])]);
+
+    // HCC2 has no coefficient so it is left out of the score
+    let scores: Vec<(&str, &str, Vec<&str>)> = view.scores().map(|(score, prefix, ids)| {
+        (score, prefix, ids.iter().map(|id| view.variable_name(*id)).collect())
+    }).collect();
+    assert_eq!(scores, vec![("SCORE_COMMUNITY_NA", "CNA", vec!["F65_69", "HCC17"])]);
+}
+
+#[test]
+fn rejects_truncated_or_foreign_words() {
+    let words = CompiledRAFTables::compile(&_tiny_public_inputs()).unwrap().to_words();
+    assert!(matches!(CompiledTablesView::new(&words[..words.len() - 1]), Err(CompiledTablesError::Truncated { .. })));
+    assert!(matches!(CompiledTablesView::new(&[1, 2, 3]), Err(CompiledTablesError::UnsupportedFormat { .. })));
+}
+
+#[test]
+fn rejects_hierarchies_that_cannot_be_ordered() {
+    let mut inputs = _tiny_public_inputs();
+    inputs.hcc_hierarchies.insert("HCC18".to_string(), vec!["HCC17".to_string()]);
+    let cycle = vec!["HCC17".to_string(), "HCC18".to_string(), "HCC17".to_string()];
+    assert_eq!(CompiledRAFTables::compile(&inputs), Err(CompiledTablesError::CyclicHierarchy(cycle)));
+
+    let mut inputs = _tiny_public_inputs();
+    inputs.hcc_labels.insert("HCCX".to_string(), "HCCX label".to_string());
+    inputs.hcc_hierarchies.insert("HCC2".to_string(), vec!["HCCX".to_string()]);
+    assert_eq!(CompiledRAFTables::compile(&inputs), Err(CompiledTablesError::UnknownHcc("HCCX".to_string())));
+}
+
+#[test]
+fn rejects_counts_past_the_end_of_the_words() {
+    let words = CompiledRAFTables::compile(&_tiny_public_inputs()).unwrap().to_words();
+
+    // The hcc string table count is the word after the header
+    let mut corrupt = words.clone();
+    corrupt[3] = u32::MAX;
+    assert!(CompiledTablesView::new(&corrupt).is_err());
+
+    // So is the variable count, which sizes the coefficients with the model count
+    let hcc_bytes_at = 3 + 1 + CompiledTablesView::new(&words).unwrap().hcc_count() + 1;
+    let mut corrupt = words.clone();
+    corrupt[hcc_bytes_at + 1 + words[hcc_bytes_at] as usize] = u32::MAX;
+    assert!(CompiledTablesView::new(&corrupt).is_err());
+}
+
+#[test]
+fn can_compile_published_py2023_tables() {
+    use crate::interactions::InteractionDefinitions;
+    use crate::utils::{get_cms_data_dir, read_dx_to_cc, read_hcc_coefficients, read_hcc_labels, read_hier, read_model_definition};
+    let path = get_cms_data_dir("PY2023");
+    let inputs = PublicRAFInputs {
+        hcc_coefficients: read_hcc_coefficients(&(path.clone() + "/C2824T2N.csv")).unwrap(),
+        hcc_hierarchies: read_hier(&(path.clone() + "/V28115H1.TXT")).unwrap(),
+        hcc_labels: read_hcc_labels(&(path.clone() + "/V28115L3.TXT")).unwrap(),
+        dx_to_cc: read_dx_to_cc(&(path.clone() + "/F2823T2N_FY22FY23.TXT")).unwrap(),
+        interactions: InteractionDefinitions::v28(),
+        model: read_model_definition(&[&(path.clone() + "/V2823T2P.TXT"), &(path + "/V2823T2M.TXT")]).unwrap(),
+        norm_factor: 1.0,
+    };
+    let compiled = CompiledRAFTables::compile(&inputs).unwrap();
+    let words = compiled.to_words();
+    let view = CompiledTablesView::new(&words).unwrap();
+
+    assert_eq!(view.hcc_count(), 115);
+    assert_eq!(view.coefficient("CNA", "HCC381"), Some(1.075));
+    assert_eq!(view.coefficient("INS", "DIABETES_HF_V28"), Some(0.209));
+    let superiors: Vec<u32> = view.hierarchy_statements().map(|x| x.0).collect();
+    assert_eq!(superiors.len(), 60);
+    assert_eq!((superiors[0], superiors[59]), (17, 405));
+    let scores: Vec<(&str, &str)> = view.scores().map(|x| (x.0, x.1)).collect();
+    assert_eq!(scores.len(), 9);
+    assert_eq!(scores[0], ("SCORE_COMMUNITY_NA", "CNA"));
+    // &COMM_REGA, &COMM_REGD, &INST_REG and &NE_REG
+    assert_eq!(compiled.regression_lists.len(), 4);
+    assert_eq!(compiled.payment_hccs.len(), 115);
+    assert!(view.is_payment_hcc("HCC463") && !view.is_payment_hcc("CC463"));
+    // The compiled form must be far smaller than the serialized string maps
+    assert!(words.len() < 5_000);
+}
+
+#[test]
+fn public_inputs_digest_covers_every_input() {
+    use crate::merkle::Sha2Hasher;
+
+    let dx_table = DxTableCommitment { root: [3; 32], rows: 10 };
+    let interactions = InteractionDefinitions::default();
+    let digest = public_inputs_digest::<Sha2Hasher>(&[1; 32], &dx_table, &interactions);
+    assert_ne!(digest, public_inputs_digest::<Sha2Hasher>(&[2; 32], &dx_table, &interactions));
+    assert_ne!(digest, public_inputs_digest::<Sha2Hasher>(&[1; 32], &DxTableCommitment { root: [4; 32], rows: 10 }, &interactions));
+    assert_ne!(digest, public_inputs_digest::<Sha2Hasher>(&[1; 32], &DxTableCommitment { root: [3; 32], rows: 11 }, &interactions));
+
+    let mut other = interactions.clone();
+    other.groups.insert("DIABETES".to_string(), vec!["HCC38".to_string()]);
+    assert_ne!(digest, public_inputs_digest::<Sha2Hasher>(&[1; 32], &dx_table, &other));
+}
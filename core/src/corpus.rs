@@ -0,0 +1,288 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use csv::{ReaderBuilder, StringRecord};
+use crate::error::RAFDataError;
+use crate::model::ModelDefinition;
+use crate::PrivateRAFInput;
+
+// Regression corpus of hand-derived expected values.
+//
+// fixtures/regression_corpus holds synthetic beneficiaries in the shape of the SAS person and
+// diagnosis files, with the score variables and the regression variables expected for each of
+// them. The expected values were worked out by hand from the SAS macros; they are not SAS
+// output and only P01 has been checked, see the header of each file. A run of the engine
+// (natively or in the Guest) is diffed against them score by score and variable by variable,
+// so any drift names the person and the variable that moved. Passing the corpus does not show
+// agreement with SAS until the values are replaced by a SAS run or signed off person by person.
+//
+//    persons.csv              id,age,sex,orec,medicaid,ltimcaid,eligibility,description
+//    diagnoses.csv            id,dx
+//    expected_scores.csv      id,<one column per score variable>
+//    expected_variables.csv   id,<regression variables set, space separated>
+
+// Largest difference allowed between a computed and an expected score, the expected values
+// are rounded to 6 decimals and the engine sums coefficients in f32
+pub const SCORE_TOLERANCE: f32 = 1e-4;
+
+/// A beneficiary of the corpus with the values expected for it
+#[derive(Debug)]
+pub struct CorpusCase {
+    pub id: String,
+
+    // What the case exercises, e.g. "heart patch drops CC223 alone"
+    pub description: String,
+
+    pub input: PrivateRAFInput,
+
+    // Score variable -> expected score, e.g. SCORE_COMMUNITY_NA
+    pub expected_scores: BTreeMap<String, f32>,
+
+    // Regression variables expected to be set, sorted
+    pub expected_variables: BTreeSet<String>,
+}
+
+/// A score or variable of a case that differs from the expected value
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorpusMismatch {
+    pub id: String,
+
+    // Score or regression variable name
+    pub name: String,
+
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for CorpusMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} expected {}, found {}", self.id, self.name, self.expected, self.actual)
+    }
+}
+
+/*
+    Reads one of the corpus files, skipping the '#' provenance comments, into
+    (line, record) pairs after the header.
+*/
+fn read_corpus_file(dir: &str, name: &str) -> Result<(StringRecord, Vec<(usize, StringRecord)>), RAFDataError> {
+
+    let path = format!("{}/{}", dir, name);
+    let csv_error = |source: csv::Error| RAFDataError::Csv {
+        path: path.clone(),
+        line: source.position().map(|p| p.line()),
+        source,
+    };
+    let mut reader = ReaderBuilder::new()
+        .comment(Some(b'#'))
+        .from_path(&path)
+        .map_err(csv_error)?;
+    let header = reader.headers().map_err(csv_error)?.clone();
+    let mut records = vec![];
+    for result in reader.records() {
+        let record = result.map_err(csv_error)?;
+        let line = record.position().map_or(0, |p| p.line() as usize);
+        records.push((line, record));
+    }
+    if records.is_empty() {
+        return Err(RAFDataError::Empty { path });
+    }
+    Ok((header, records))
+}
+
+/*
+    Reads the corpus in `dir`. Every person needs a row of expected scores and of expected
+    variables; diagnoses are optional, a person without any is scored on demographics only.
+*/
+pub fn read_regression_corpus(dir: &str) -> Result<Vec<CorpusCase>, RAFDataError> {
+
+    let parse_error = |name: &str, line: usize, message: String| RAFDataError::Parse {
+        path: format!("{}/{}", dir, name),
+        line,
+        message,
+    };
+
+    let (_, diagnoses) = read_corpus_file(dir, "diagnoses.csv")?;
+    let mut diagnosis_codes = BTreeMap::<String, Vec<String>>::new();
+    for (_, record) in &diagnoses {
+        diagnosis_codes.entry(record[0].to_string()).or_default().push(record[1].to_string());
+    }
+
+    let (header, scores) = read_corpus_file(dir, "expected_scores.csv")?;
+    let mut expected_scores = BTreeMap::<String, BTreeMap<String, f32>>::new();
+    for (line, record) in &scores {
+        let mut values = BTreeMap::new();
+        for (name, value) in header.iter().zip(record.iter()).skip(1) {
+            let value = value
+                .parse::<f32>()
+                .map_err(|_| parse_error("expected_scores.csv", *line, format!("{} is not a score: {:?}", name, value)))?;
+            values.insert(name.to_string(), value);
+        }
+        expected_scores.insert(record[0].to_string(), values);
+    }
+
+    let (_, variables) = read_corpus_file(dir, "expected_variables.csv")?;
+    let expected_variables: BTreeMap<String, BTreeSet<String>> = variables
+        .iter()
+        .map(|(_, record)| (record[0].to_string(), record[1].split_whitespace().map(|x| x.to_string()).collect()))
+        .collect();
+
+    let (_, persons) = read_corpus_file(dir, "persons.csv")?;
+    let mut cases = vec![];
+    for (line, record) in &persons {
+        let id = record[0].to_string();
+        let age = record[1]
+            .parse::<i32>()
+            .map_err(|_| parse_error("persons.csv", *line, format!("age is not a number: {:?}", &record[1])))?;
+        let input = PrivateRAFInput {
+            diagnosis_codes: diagnosis_codes.remove(&id).unwrap_or_default(),
+            age,
+            sex: record[2].to_string(),
+            eligibility_code: record[6].to_string(),
+            entitlement_reason_code: record[3].to_string(),
+            medicaid_status: &record[4] == "1",
+            long_term_institutionalized: &record[5] == "1",
+        };
+        let missing = |file: &str| parse_error(file, *line, format!("no expected output for {}", id));
+        cases.push(CorpusCase {
+            expected_scores: expected_scores.remove(&id).ok_or_else(|| missing("expected_scores.csv"))?,
+            expected_variables: expected_variables.get(&id).cloned().ok_or_else(|| missing("expected_variables.csv"))?,
+            description: record[7].to_string(),
+            id,
+            input,
+        });
+    }
+
+    if let Some(id) = diagnosis_codes.keys().next() {
+        return Err(parse_error("diagnoses.csv", 0, format!("diagnoses for unknown person {}", id)));
+    }
+    Ok(cases)
+}
+
+/// Every expected score missing from `actual` or off by more than `SCORE_TOLERANCE`
+pub fn diff_scores(case: &CorpusCase, actual: &BTreeMap<String, f32>) -> Vec<CorpusMismatch> {
+
+    let mut mismatches = vec![];
+    for (name, expected) in &case.expected_scores {
+        let mismatch = match actual.get(name) {
+            Some(value) if (value - expected).abs() <= SCORE_TOLERANCE => continue,
+            Some(value) => format!("{:.6}", value),
+            None => "no score".to_string(),
+        };
+        mismatches.push(CorpusMismatch {
+            id: case.id.clone(),
+            name: name.clone(),
+            expected: format!("{:.6}", expected),
+            actual: mismatch,
+        });
+    }
+    return mismatches;
+}
+
+/*
+    Compares the regression variables set for a case with the expected ones. `attributes` may hold
+    more than regression variables (e.g. DISABL), so only the variables of the model's
+    %SCOREMAC lists are compared.
+*/
+pub fn diff_variables(case: &CorpusCase, attributes: &[String], model: &ModelDefinition) -> Vec<CorpusMismatch> {
+
+    let regression_variables: BTreeSet<&str> = model
+        .scores
+        .iter()
+        .flat_map(|x| x.variables.iter().map(|x| x.as_str()))
+        .collect();
+    let actual: BTreeSet<String> = attributes
+        .iter()
+        .filter(|x| regression_variables.contains(x.as_str()))
+        .cloned()
+        .collect();
+
+    let set_or_unset = |set: bool| if set { "set" } else { "unset" }.to_string();
+    return case
+        .expected_variables
+        .symmetric_difference(&actual)
+        .map(|name| CorpusMismatch {
+            id: case.id.clone(),
+            name: name.clone(),
+            expected: set_or_unset(case.expected_variables.contains(name)),
+            actual: set_or_unset(actual.contains(name)),
+        })
+        .collect();
+}
+
+#[cfg(test)]
+fn _corpus_dir() -> String {
+    format!("{}/fixtures/regression_corpus", env!("CARGO_MANIFEST_DIR"))
+}
+
+#[test]
+fn can_read_regression_corpus() {
+    let cases = read_regression_corpus(&_corpus_dir()).unwrap();
+    assert!(cases.len() >= 20);
+
+    let first = &cases[0];
+    assert_eq!(first.id, "P01");
+    assert_eq!(first.input.diagnosis_codes, vec!["E1122", "E119", "I5020"]);
+    assert_eq!(first.expected_scores.len(), 9);
+    assert!(first.expected_variables.contains("DIABETES_HF_V28"));
+
+    // A person without diagnosis rows is scored on demographics only
+    let no_dx = cases.iter().find(|x| x.id == "P05").unwrap();
+    assert!(no_dx.input.diagnosis_codes.is_empty());
+}
+
+#[test]
+fn reports_corpus_differences_by_person_and_name() {
+    let cases = read_regression_corpus(&_corpus_dir()).unwrap();
+    let case = &cases[0];
+
+    let mut scores = case.expected_scores.clone();
+    assert!(diff_scores(case, &scores).is_empty());
+    *scores.get_mut("SCORE_INSTITUTIONAL").unwrap() += 0.01;
+    scores.remove("SCORE_NEW_ENROLLEE");
+    let mismatches = diff_scores(case, &scores);
+    assert_eq!(mismatches.len(), 2);
+    assert_eq!(mismatches[0].to_string(), "P01: SCORE_INSTITUTIONAL expected 1.930000, found 1.940000");
+    assert_eq!(mismatches[1].actual, "no score");
+
+    let cms_dir = crate::utils::get_cms_data_dir("PY2023");
+    let model = crate::utils::read_model_definition(&[&format!("{}/V2823T2P.TXT", cms_dir), &format!("{}/V2823T2M.TXT", cms_dir)]).unwrap();
+    let mut attributes: Vec<String> = case.expected_variables.iter().cloned().collect();
+    attributes.push("DISABL".to_string());
+    assert!(diff_variables(case, &attributes, &model).is_empty());
+    attributes.retain(|x| x != "D2");
+    attributes.push("D3".to_string());
+    let mismatches = diff_variables(case, &attributes, &model);
+    assert_eq!(mismatches.iter().map(|x| x.to_string()).collect::<Vec<_>>(),
+               vec!["P01: D2 expected set, found unset", "P01: D3 expected unset, found set"]);
+}
+
+/*
+    Scores every person of the corpus with the engine the Guest runs and diffs every score
+    and regression variable against the hand-derived expected values (not SAS output).
+*/
+#[test]
+fn native_engine_matches_regression_corpus() {
+    use crate::compiled::{CompiledRAFTables, CompiledTablesView};
+    use crate::engine::ScoringContext;
+    use crate::utils::{get_cms_data_dir, load_public_inputs};
+
+    let public_inputs = load_public_inputs(&get_cms_data_dir("PY2023")).unwrap();
+    let words = CompiledRAFTables::compile(&public_inputs).unwrap().to_words();
+    let context = ScoringContext::new(CompiledTablesView::new(&words).unwrap(), &public_inputs.interactions);
+
+    let mut mismatches = vec![];
+    for case in read_regression_corpus(&_corpus_dir()).unwrap() {
+        let mapped_hccs: Vec<String> = case
+            .input
+            .diagnosis_codes
+            .iter()
+            .filter_map(|dx| public_inputs.dx_to_cc.get(dx))
+            .flatten()
+            .cloned()
+            .collect();
+        let scores = context.score(&case.input, &mapped_hccs);
+        mismatches.extend(diff_scores(&case, &scores.raf_scores));
+        mismatches.extend(diff_variables(&case, &scores.segment_variables(&case.input.eligibility_code), &public_inputs.model));
+    }
+    let report: Vec<String> = mismatches.iter().map(|x| x.to_string()).collect();
+    assert!(report.is_empty(), "{}", report.join("\n"));
+}
@@ -0,0 +1,152 @@
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+// Normalization and validation of submitted ICD-10-CM diagnosis codes.
+//
+// dx_to_cc is keyed by undotted, upper case codes (E119), while claims and charts often carry
+// E11.9, e119 or "E119 ". The Guest normalizes the submitted codes with `normalize_diagnosis_codes`
+// before any lookup, so the proof covers the normalization, and the Host builds the dx_to_cc
+// lookups from the same list. `validate_diagnosis_codes` reports the codes that were dropped or
+// have no mapping; the report stays on the Host and is never committed to the journal.
+
+/// Diagnosis code without dots or whitespace, in upper case, e.g. " e11.9" -> "E119"
+pub fn normalize_dx(code: &str) -> String {
+    code.chars()
+        .filter(|c| *c != '.' && !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/*
+    Shape of a normalized ICD-10-CM code: a letter, a digit, then one to five letters or
+    digits (A021, C4A0, S061X3A). Anything else can never be in dx_to_cc.
+*/
+pub fn is_well_formed_dx(code: &str) -> bool {
+    let bytes = code.as_bytes();
+    (3..=7).contains(&bytes.len())
+        && bytes[0].is_ascii_uppercase()
+        && bytes[1].is_ascii_digit()
+        && bytes[2..].iter().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
+
+/*
+    Codes the Guest scores: normalized, well formed and without duplicates, in the order
+    they were first submitted.
+*/
+pub fn normalize_diagnosis_codes(codes: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = vec![];
+    for code in codes {
+        let code = normalize_dx(code);
+        if is_well_formed_dx(&code) && !normalized.contains(&code) {
+            normalized.push(code);
+        }
+    }
+    return normalized;
+}
+
+/// Why a submitted diagnosis code does not contribute an HCC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DxIssueKind {
+    // Not an ICD-10-CM code once normalized, dropped before scoring
+    Malformed,
+
+    // Well formed but absent from the fiscal year dx_to_cc mapping: either not a valid code
+    // for that year or a valid code that maps to no payment HCC
+    Unmapped,
+}
+
+/// A submitted code and what is wrong with it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DxIssue {
+    // The code as submitted
+    pub code: String,
+
+    pub normalized: String,
+    pub kind: DxIssueKind,
+}
+
+impl fmt::Display for DxIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            DxIssueKind::Malformed => write!(f, "{:?} is not an ICD-10-CM code", self.code),
+            DxIssueKind::Unmapped => write!(f, "{:?} ({}) is not in the dx_to_cc mapping", self.code, self.normalized),
+        }
+    }
+}
+
+/// Outcome of normalizing the diagnosis codes of a beneficiary, kept on the Host
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DxValidationReport {
+    // The codes the Guest scores, see `normalize_diagnosis_codes`
+    pub codes: Vec<String>,
+
+    // Submitted codes that were merged into an earlier one once normalized
+    pub duplicates: usize,
+
+    pub issues: Vec<DxIssue>,
+}
+
+impl DxValidationReport {
+    pub fn has_issues(&self) -> bool {
+        !self.issues.is_empty()
+    }
+}
+
+/// Normalizes `codes` and reports the malformed ones and those dx_to_cc does not map
+pub fn validate_diagnosis_codes(codes: &[String], dx_to_cc: &BTreeMap<String, Vec<String>>) -> DxValidationReport {
+
+    let mut report = DxValidationReport::default();
+    for code in codes {
+        let normalized = normalize_dx(code);
+        let kind = if !is_well_formed_dx(&normalized) {
+            DxIssueKind::Malformed
+        } else if report.codes.contains(&normalized) {
+            report.duplicates += 1;
+            continue;
+        } else {
+            report.codes.push(normalized.clone());
+            if dx_to_cc.contains_key(&normalized) {
+                continue;
+            }
+            DxIssueKind::Unmapped
+        };
+        report.issues.push(DxIssue { code: code.to_string(), normalized, kind });
+    }
+    return report;
+}
+
+#[cfg(test)]
+fn _strings(codes: &[&str]) -> Vec<String> {
+    codes.iter().map(|x| x.to_string()).collect()
+}
+
+#[test]
+fn can_normalize_diagnosis_codes() {
+    assert_eq!(normalize_dx(" e11.9\t"), "E119");
+    assert_eq!(normalize_dx("S06.1X3A"), "S061X3A");
+    assert!(is_well_formed_dx("C4A0") && is_well_formed_dx("A02"));
+    assert!(!is_well_formed_dx("E1") && !is_well_formed_dx("1E19") && !is_well_formed_dx("E119-") && !is_well_formed_dx("S061X3A0"));
+
+    let codes = _strings(&["E11.9", "e119", "E119 ", "I50.20", "", "N/A", "I5020"]);
+    assert_eq!(normalize_diagnosis_codes(&codes), _strings(&["E119", "I5020"]));
+}
+
+#[test]
+fn reports_malformed_and_unmapped_codes() {
+    let mut dx_to_cc = BTreeMap::new();
+    dx_to_cc.insert("E119".to_string(), _strings(&["HCC38"]));
+    dx_to_cc.insert("I5020".to_string(), _strings(&["HCC226"]));
+
+    let report = validate_diagnosis_codes(&_strings(&["E11.9", "e119", "Z00.00", "??", "I5020"]), &dx_to_cc);
+    assert_eq!(report.codes, _strings(&["E119", "Z0000", "I5020"]));
+    assert_eq!(report.codes, normalize_diagnosis_codes(&_strings(&["E11.9", "e119", "Z00.00", "??", "I5020"])));
+    assert_eq!(report.duplicates, 1);
+    assert_eq!(report.issues.iter().map(|x| (x.code.as_str(), x.kind)).collect::<Vec<_>>(),
+               vec![("Z00.00", DxIssueKind::Unmapped), ("??", DxIssueKind::Malformed)]);
+    assert_eq!(report.issues[0].to_string(), "\"Z00.00\" (Z0000) is not in the dx_to_cc mapping");
+    assert!(!validate_diagnosis_codes(&_strings(&["I50.20"]), &dx_to_cc).has_issues());
+}
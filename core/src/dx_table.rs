@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use crate::merkle::{Hash, MerkleHasher, MerkleProof, MerkleTree};
+
+// Merkle commitment over the ICD-10 to HCC mapping (dx_to_cc).
+//
+// Rows are committed in sorted diagnosis code order. The Host hands the Guest one
+// `DxLookup` per patient diagnosis: an inclusion proof of the row when the code is
+// mapped, or the two neighbouring rows when it is not, which proves the code falls
+// between consecutive rows and therefore has no mapping. The Guest commits the
+// `DxTableCommitment` it checked against to the journal.
+
+/// Root and row count of the committed dx_to_cc table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DxTableCommitment {
+    pub root: Hash,
+    pub rows: u32,
+}
+
+/// A single dx_to_cc row: a diagnosis code and the HCCs it maps to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DxRow {
+    pub dx: String,
+    pub hccs: Vec<String>,
+}
+
+impl DxRow {
+
+    /// Bytes hashed into the leaf: the code, a NUL, then the HCCs separated by commas
+    pub fn leaf_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.dx.as_bytes().to_vec();
+        bytes.push(0);
+        bytes.extend_from_slice(self.hccs.join(",").as_bytes());
+        bytes
+    }
+
+    pub fn leaf_hash<H: MerkleHasher>(&self) -> Hash {
+        H::hash_leaf(&self.leaf_bytes())
+    }
+}
+
+/// A row together with its inclusion proof
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DxRowProof {
+    pub row: DxRow,
+    pub proof: MerkleProof,
+}
+
+impl DxRowProof {
+    fn verify<H: MerkleHasher>(&self, commitment: &DxTableCommitment) -> bool {
+        self.proof.verify::<H>(self.row.leaf_hash::<H>(), commitment.rows, &commitment.root)
+    }
+}
+
+/// Evidence for the mapping of one diagnosis code
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DxLookup {
+    // The code has a row in the table
+    Mapped(DxRowProof),
+
+    // The code sorts strictly between two consecutive rows (None past either end)
+    Unmapped { lower: Option<DxRowProof>, upper: Option<DxRowProof> },
+}
+
+/// Reasons a `DxLookup` does not prove anything about a diagnosis code
+#[derive(Debug, Clone, PartialEq)]
+pub enum DxProofError {
+    // The row is for a different diagnosis code than the one looked up
+    WrongCode { expected: String, found: String },
+
+    // An inclusion proof does not lead to the committed root
+    InvalidInclusion { dx: String },
+
+    // The neighbouring rows are not consecutive or do not surround the code
+    InvalidExclusion { dx: String },
+
+    // The number of lookups does not match the number of diagnosis codes
+    LookupCount { codes: usize, lookups: usize },
+}
+
+impl fmt::Display for DxProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DxProofError::WrongCode { expected, found } => {
+                write!(f, "proof is for diagnosis {} but {} was looked up", found, expected)
+            }
+            DxProofError::InvalidInclusion { dx } => write!(f, "inclusion proof for {} does not match the root", dx),
+            DxProofError::InvalidExclusion { dx } => write!(f, "exclusion proof for {} is not valid", dx),
+            DxProofError::LookupCount { codes, lookups } => {
+                write!(f, "expected one lookup per diagnosis code, found {} lookups for {} codes", lookups, codes)
+            }
+        }
+    }
+}
+
+impl DxLookup {
+
+    /// Checks the lookup for `dx` against the committed table and returns the HCCs it maps to,
+    /// None when the code is proven to be absent from the table.
+    pub fn verify<H: MerkleHasher>(&self, dx: &str, commitment: &DxTableCommitment) -> Result<Option<&[String]>, DxProofError> {
+        match self {
+            DxLookup::Mapped(row_proof) => {
+                if row_proof.row.dx != dx {
+                    return Err(DxProofError::WrongCode { expected: dx.to_string(), found: row_proof.row.dx.clone() });
+                }
+                if !row_proof.verify::<H>(commitment) {
+                    return Err(DxProofError::InvalidInclusion { dx: dx.to_string() });
+                }
+                Ok(Some(&row_proof.row.hccs))
+            }
+            DxLookup::Unmapped { lower, upper } => {
+                let invalid = || DxProofError::InvalidExclusion { dx: dx.to_string() };
+                for neighbour in [lower, upper].into_iter().flatten() {
+                    if !neighbour.verify::<H>(commitment) {
+                        return Err(invalid());
+                    }
+                }
+                let consecutive = match (lower, upper) {
+                    (Some(l), Some(u)) => {
+                        l.row.dx.as_str() < dx && dx < u.row.dx.as_str() && l.proof.index + 1 == u.proof.index
+                    }
+                    (None, Some(u)) => dx < u.row.dx.as_str() && u.proof.index == 0,
+                    (Some(l), None) => l.row.dx.as_str() < dx && l.proof.index + 1 == commitment.rows,
+                    (None, None) => commitment.rows == 0,
+                };
+                if !consecutive {
+                    return Err(invalid());
+                }
+                Ok(None)
+            }
+        }
+    }
+}
+
+/*
+    HCCs of every diagnosis code, checking the lookup of each code (or the proof of its
+    absence) against the committed table. Codes are expected to be normalized already.
+*/
+pub fn verified_hccs<H: MerkleHasher>(diagnosis_codes: &[String], lookups: &[DxLookup], commitment: &DxTableCommitment) -> Result<Vec<String>, DxProofError> {
+    if diagnosis_codes.len() != lookups.len() {
+        return Err(DxProofError::LookupCount { codes: diagnosis_codes.len(), lookups: lookups.len() });
+    }
+    let mut hccs = vec![];
+    for (dx, lookup) in diagnosis_codes.iter().zip(lookups) {
+        if let Some(mapped) = lookup.verify::<H>(dx, commitment)? {
+            hccs.extend(mapped.iter().cloned());
+        }
+    }
+    Ok(hccs)
+}
+
+/// Host side copy of the whole table and its Merkle tree
+pub struct DxMerkleTable {
+    rows: Vec<DxRow>,
+    tree: MerkleTree,
+}
+
+impl DxMerkleTable {
+
+    pub fn from_dx_to_cc<H: MerkleHasher>(dx_to_cc: &BTreeMap<String, Vec<String>>) -> Self {
+        let rows: Vec<DxRow> = dx_to_cc
+            .iter()
+            .map(|(dx, hccs)| DxRow { dx: dx.clone(), hccs: hccs.clone() })
+            .collect();
+        let tree = MerkleTree::from_leaves::<H>(rows.iter().map(|row| row.leaf_hash::<H>()).collect());
+        DxMerkleTable { rows, tree }
+    }
+
+    pub fn commitment(&self) -> DxTableCommitment {
+        DxTableCommitment { root: self.tree.root(), rows: self.rows.len() as u32 }
+    }
+
+    fn row_proof(&self, index: usize) -> DxRowProof {
+        DxRowProof { row: self.rows[index].clone(), proof: self.tree.proof(index) }
+    }
+
+    /// Evidence for the mapping of `dx`, to be checked by the Guest
+    pub fn lookup(&self, dx: &str) -> DxLookup {
+        match self.rows.binary_search_by(|row| row.dx.as_str().cmp(dx)) {
+            Ok(index) => DxLookup::Mapped(self.row_proof(index)),
+            Err(index) => DxLookup::Unmapped {
+                lower: index.checked_sub(1).map(|i| self.row_proof(i)),
+                upper: (index < self.rows.len()).then(|| self.row_proof(index)),
+            },
+        }
+    }
+
+    /// One lookup per diagnosis code, in the same order
+    pub fn lookups(&self, diagnosis_codes: &[String]) -> Vec<DxLookup> {
+        diagnosis_codes.iter().map(|dx| self.lookup(dx)).collect()
+    }
+}
+
+#[cfg(test)]
+fn _small_dx_table() -> DxMerkleTable {
+    let mut dx_to_cc = BTreeMap::new();
+    dx_to_cc.insert("A021".to_string(), vec!["HCC2".to_string()]);
+    dx_to_cc.insert("B20".to_string(), vec!["HCC1".to_string()]);
+    dx_to_cc.insert("C7800".to_string(), vec!["HCC17".to_string()]);
+    dx_to_cc.insert("E1122".to_string(), vec!["HCC37".to_string(), "HCC329".to_string()]);
+    dx_to_cc.insert("I5020".to_string(), vec!["HCC226".to_string()]);
+    DxMerkleTable::from_dx_to_cc::<crate::merkle::Sha2Hasher>(&dx_to_cc)
+}
+
+#[test]
+fn can_verify_mapped_and_unmapped_codes() {
+    use crate::merkle::Sha2Hasher;
+    let table = _small_dx_table();
+    let commitment = table.commitment();
+
+    let e1122 = table.lookup("E1122");
+    assert_eq!(e1122.verify::<Sha2Hasher>("E1122", &commitment).unwrap().unwrap(), ["HCC37", "HCC329"]);
+
+    for dx in ["A0000", "B99", "Z9999"] {
+        assert_eq!(table.lookup(dx).verify::<Sha2Hasher>(dx, &commitment), Ok(None));
+    }
+}
+
+#[test]
+fn rejects_forged_dx_lookups() {
+    use crate::merkle::Sha2Hasher;
+    let table = _small_dx_table();
+    let commitment = table.commitment();
+
+    // A valid row cannot stand in for another code
+    let b20 = table.lookup("B20");
+    assert!(matches!(b20.verify::<Sha2Hasher>("C7800", &commitment), Err(DxProofError::WrongCode { .. })));
+
+    // Changing the HCCs of a row breaks its inclusion proof
+    let mut forged = table.lookup("C7800");
+    if let DxLookup::Mapped(row_proof) = &mut forged {
+        row_proof.row.hccs = vec!["HCC1".to_string()];
+    }
+    assert!(matches!(forged.verify::<Sha2Hasher>("C7800", &commitment), Err(DxProofError::InvalidInclusion { .. })));
+
+    // A mapped code cannot be hidden behind non-adjacent neighbours
+    let hidden = DxLookup::Unmapped {
+        lower: match table.lookup("B20") { DxLookup::Mapped(p) => Some(p), _ => None },
+        upper: match table.lookup("E1122") { DxLookup::Mapped(p) => Some(p), _ => None },
+    };
+    assert!(matches!(hidden.verify::<Sha2Hasher>("C7800", &commitment), Err(DxProofError::InvalidExclusion { .. })));
+}
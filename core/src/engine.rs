@@ -0,0 +1,765 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::compiled::CompiledTablesView;
+use crate::hierarchy::{apply_hierarchies, hcc_number};
+use crate::interactions::InteractionDefinitions;
+use crate::PrivateRAFInput;
+
+// Scoring engine of the V28 CMS-HCC model.
+//
+// Everything needed to score a beneficiary is passed in explicitly through `ScoringContext`
+// and the function arguments; there is no global state and only `core` / `alloc` are used,
+// so the Guest and a native preview on the Host run exactly the same code.
+
+/// Public tables a beneficiary is scored against
+#[derive(Clone, Copy)]
+pub struct ScoringContext<'a> {
+    pub tables: CompiledTablesView<'a>,
+
+    // Diagnostic groups and interaction variables of the model
+    pub interactions: &'a InteractionDefinitions,
+}
+
+/// Result of scoring one beneficiary
+#[derive(Debug, Clone, PartialEq)]
+pub struct BeneficiaryScores {
+    // Payment HCCs left after the hierarchies
+    pub hccs: Vec<String>,
+
+    // Every regression variable set for the beneficiary: age/sex cells, HCCs, interactions, ...
+    // The count variables (D1 .. D10P) depend on the segment and are in `count_variables`.
+    pub attributes: Vec<String>,
+
+    // Count variable each score variable was computed with, absent when the beneficiary has
+    // no payment HCC in the regression of that score
+    pub count_variables: BTreeMap<String, String>,
+
+    // Normalized score for each score variable, e.g. SCORE_COMMUNITY_NA
+    pub raf_scores: BTreeMap<String, f32>,
+}
+
+impl BeneficiaryScores {
+
+    /// `attributes` with the count variable of the segment the beneficiary is paid on, e.g. CNA
+    pub fn segment_variables(&self, eligibility_code: &str) -> Vec<String> {
+        let mut variables = self.attributes.clone();
+        if let Some(count_variable) = segment_score(eligibility_code).and_then(|x| self.count_variables.get(x)) {
+            variables.push(count_variable.clone());
+        }
+        return variables;
+    }
+}
+
+impl<'a> ScoringContext<'a> {
+
+    pub fn new(tables: CompiledTablesView<'a>, interactions: &'a InteractionDefinitions) -> Self {
+        ScoringContext { tables, interactions }
+    }
+
+    /// Scores a beneficiary from the HCCs their diagnosis codes map to
+    pub fn score(&self, private_input: &PrivateRAFInput, mapped_hccs: &[String]) -> BeneficiaryScores {
+        self.score_with_phases(private_input, mapped_hccs, &mut |_| {})
+    }
+
+    /// Same as `score`, calling `phase` with a short message as each step completes
+    pub fn score_with_phases(&self, private_input: &PrivateRAFInput, mapped_hccs: &[String], phase: &mut dyn FnMut(&str)) -> BeneficiaryScores {
+
+        let tables = &self.tables;
+
+        // Apply Age & Sex edits
+        let mut age_sex_map = age_sex_v2(private_input.age, &private_input.sex, &private_input.entitlement_reason_code);
+        if private_input.long_term_institutionalized {
+            age_sex_map.push(String::from("LTIMCAID"));
+        }
+
+        // OriginallyDisabled_Female = ORIGDS*(SEX='2'); OriginallyDisabled_Male = ORIGDS*(SEX='1');
+        if age_sex_map.iter().any(|x| x == "ORIGDS") {
+            let sex = if private_input.sex == "M" { "Male" } else { "Female" };
+            age_sex_map.push(format!("OriginallyDisabled_{}", sex));
+        }
+
+        // New enrollee cells (Medicaid x originally disabled x age/sex)
+        let ne_cells = new_enrollee_cells(&age_sex_map, private_input.age, &private_input.entitlement_reason_code, private_input.medicaid_status);
+
+        phase("Got age sex map ");
+
+        // TODO: Apply ICD-10 edits (MCE data should be an input parameter)
+
+        // Apply the heart patch and the V28115H1 hierarchies to the patient's CCs
+        let patient_ccs: BTreeSet<u32> = mapped_hccs.iter().filter_map(|x| hcc_number(x)).collect();
+        let final_hcc_list: Vec<String> = apply_hierarchies(&patient_ccs, tables.hierarchy_statements())
+            .iter()
+            .map(|x| format!("HCC{}", x))
+            .collect();
+
+        phase("Applied hierarchy to HCC list");
+
+        // Apply interactions to HCC list, with the age/sex variables (DISABL, ORIGDS) as flags.
+        // The payment HCC count (D1 .. D10P) is set by each score over its own regression.
+        let final_interactions = apply_interactions(self.interactions, &final_hcc_list, &age_sex_map);
+
+        phase("Applied interactions to HCC list");
+
+        let mut all_raf_attributes = vec![];
+        all_raf_attributes.extend(age_sex_map.iter().cloned());
+        all_raf_attributes.extend(final_hcc_list.iter().cloned());
+        all_raf_attributes.extend(final_interactions.iter().cloned());
+        all_raf_attributes.extend(ne_cells.iter().cloned());
+
+        // Apply coefficients for each scoring model, one per %SCOREMAC call of the main macro, e.g.
+        // %&SCOREMAC(PVAR=SCORE_COMMUNITY_NA,  RLIST=&COMM_REGA, CPREF=CNA_);
+        let scores: Vec<(&str, f32)> = tables
+            .scores()
+            .map(|(name, prefix, variables)| (name, get_regression_score(tables, prefix, variables, &all_raf_attributes)))
+            .collect();
+        let count_variables = tables
+            .scores()
+            .filter_map(|(name, _, variables)| Some((name.to_string(), segment_count_variable(tables, variables, &all_raf_attributes)?.to_string())))
+            .collect();
+
+        phase("Got community, institutional, new enrollee and SNP new enrollee scores");
+
+        // Normalize the scores
+        let raf_scores = scores
+            .iter()
+            .map(|(name, score)| (name.to_string(), score * tables.norm_factor()))
+            .collect();
+
+        phase("Normalized scores");
+
+        BeneficiaryScores { hccs: final_hcc_list, attributes: all_raf_attributes, count_variables, raf_scores }
+    }
+}
+
+// 1  MACRO NAME:  V28I0ED1
+//                 UDXG update V0123 for V28 model (payment HCCs only). 
+//                 ICD10 codes valid in FY20 through FY23.
+// 2  PURPOSE:     age/sex edits on ICD10: some edits are mandatory, 
+//                 others - are based on MCE list to check
+//                 if age or sex for a beneficiary is within the
+//                 range of acceptable age/sex, if not- CC is set to 
+//                 -1.0 - invalid
+// 3  PARAMETERS:  AGE   - beneficiary age variable calculated by DOB
+//                         from a person level file
+//                 SEX   - beneficiary SEX variable in a person level file
+//                 ICD10  - diagnosis variable in a diagnosis file
+
+// 4  COMMENTS:    1. Age format AGEFMT0 and sex format SEXFMT0 are 
+//                    parameters in the main macro. They have to 
+//                    correspond to the years of data
+
+//                 2. If ICD10 code does not have any restriction on age
+//                    or sex then the corresponding format puts it in "-1"
+
+//                 3. AGEL format sets lower limits for age
+//                    AGEU format sets upper limit for age
+//                    for specific edit categories:
+//                      "0"= "0 newborn (age 0)      "
+//                      "1"= "1 pediatric (age 0 -17)"
+//                      "2"= "2 maternity (age 9 -64)"
+//                      "3"= "3 adult (age 15+)      "
+
+//                 4. SEDITS - parameter for the main macro
+// *Translated from V28I0ED1.TXT for more details*
+// fn icd_10_edits() {
+
+// }
+
+// This function defines the Age & Sex grouping for the person with the given age, sex,
+// and original reason enrolled in Medicare. 
+// *Translated from AGESEXV2.TXT for more details*
+pub fn age_sex_v2(age: i32, sex: &str, orec: &str) -> Vec<String> {
+    
+    // Define a map with keys associated with the different groupings based on AGE, SEX, and OREC
+    let mut age_sex_map = BTreeMap::<String, bool>::new();
+    // Enrollee keys: F0_34 F35_44 F45_54 F55_59 F60_64 F65_69 
+    //                F70_74 F75_79 F80_84 F85_89 F90_94 F95_GT
+    //                M0_34  M35_44 M45_54 M55_59 M60_64 M65_69
+    //                M70_74 M75_79 M80_84 M85_89 M90_94 M95_GT
+    let enrollee_keys = vec![
+        "F0_34", "F35_44", "F45_54", "F55_59", "F60_64", "F65_69", "F70_74", "F75_79", "F80_84", "F85_89", "F90_94", "F95_GT",
+        "M0_34", "M35_44", "M45_54", "M55_59", "M60_64", "M65_69", "M70_74", "M75_79", "M80_84", "M85_89", "M90_94", "M95_GT"
+    ];
+    for key in &enrollee_keys { age_sex_map.insert(key.to_string(), false); }
+    let mut key_index = enrollee_keys.len();
+    if age <= 34 {
+        key_index = 0;
+    } 
+    else if age >= 35 && age < 45 {
+        key_index = 1;
+    }
+    else if age >= 45 && age < 55 {
+        key_index = 2;
+    }
+    else if age >= 55 && age < 60 {
+        key_index = 3;
+    }
+    else if age >= 60 && age < 65 {
+        key_index = 4;
+    }
+    else if age >= 65 && age < 70 {
+        key_index = 5;
+    }
+    else if age >= 70 && age < 75 {
+        key_index = 6;
+    }
+    else if age >= 75 && age < 80 {
+        key_index = 7;
+    }
+    else if age >= 80 && age < 85 {
+        key_index = 8;
+    }
+    else if age >= 85 && age < 90 {
+        key_index = 9;
+    }
+    else if age >= 90 && age < 95 {
+        key_index = 10;
+    }
+    else if age >= 95 {
+        key_index = 11;
+    }
+    if sex == "M" { key_index += 12; }
+
+    let cat_key = enrollee_keys[key_index];
+    let val = age_sex_map.get_mut(cat_key).unwrap();
+    *val = true;
+    
+    // New Enrollee keys: NEF0_34  NEF35_44 NEF45_54 NEF55_59 NEF60_64
+    //                    NEF65    NEF66    NEF67    NEF68    NEF69
+    //                    NEF70_74 NEF75_79 NEF80_84 NEF85_89 NEF90_94
+    //                    NEF95_GT
+    //                    NEM0_34  NEM35_44 NEM45_54 NEM55_59 NEM60_64
+    //                    NEM65    NEM66    NEM67    NEM68    NEM69
+    //                    NEM70_74 NEM75_79 NEM80_84 NEM85_89 NEM90_94
+    //                    NEM95_GT
+    let new_enrollee_keys = vec![
+        "NEF0_34", "NEF35_44", "NEF45_54", "NEF55_59", "NEF60_64", "NEF65", "NEF66", "NEF67", "NEF68", "NEF69", "NEF70_74", "NEF75_79", "NEF80_84", "NEF85_89", "NEF90_94", "NEF95_GT",
+        "NEM0_34", "NEM35_44", "NEM45_54", "NEM55_59", "NEM60_64", "NEM65", "NEM66", "NEM67", "NEM68", "NEM69", "NEM70_74", "NEM75_79", "NEM80_84", "NEM85_89", "NEM90_94", "NEM95_GT"
+    ];
+    for key in &new_enrollee_keys { age_sex_map.insert(key.to_string(), false); }
+    let mut key_index = new_enrollee_keys.len();
+
+    if age <= 34 {
+        key_index = 0;
+    } 
+    else if age >= 35 && age < 45 {
+        key_index = 1;
+    }
+    else if age >= 45 && age < 55 {
+        key_index = 2;
+    }
+    else if age >= 55 && age < 60 {
+        key_index = 3;
+    }
+    else if age >= 60 && age < 65 {
+        key_index = 4;
+    }
+    // if age == 64 and orec is not 0 
+    if age == 64 && orec != "0" {
+        key_index = 4;
+    }
+    else if age == 64 && orec == "0" {
+        key_index = 5;
+    }
+    else if age == 65 {
+        key_index = 5;   
+    }
+    else if age == 66 {
+        key_index = 6;
+    }
+    else if age == 67 {
+        key_index = 7
+    }
+    else if age == 68 {
+        key_index = 8;
+    }
+    else if age == 69 {
+        key_index = 9;
+    }
+    else if age >= 70 && age < 75 {
+        key_index = 10;
+    }
+    else if age >= 75 && age < 80 {
+        key_index = 11;
+    }
+    else if age >= 80 && age < 85 {
+        key_index = 12;
+    }
+    else if age >= 85 && age < 90 {
+        key_index = 13;
+    }
+    else if age >= 90 && age < 95 {
+        key_index = 14;
+    }
+    else if age >= 95 {
+        key_index = 15;
+    }
+
+    if sex == "M" { key_index += 16; }
+    let ne_cat_key = new_enrollee_keys[key_index];
+    let ne_val = age_sex_map.get_mut(ne_cat_key).unwrap();
+    *ne_val = true;
+
+    //Other keys ORIGDS  - originally disabled dummy variable
+    //           DISABL  - disabled dummy variable
+    // DISABL = (&AGEF < 65 & &OREC ne "0");
+    // %* originally disabled;
+    // ORIGDS  = (&OREC = '1')*(DISABL = 0);
+
+    let disabl = age < 65 && orec != "0";
+    age_sex_map.insert(String::from("DISABL"), disabl);
+    age_sex_map.insert(String::from("ORIGDS"), orec == "1" && !disabl);
+
+    age_sex_map.retain(|_, v| *v);
+
+    return age_sex_map.keys().map(|s| s.to_string()).collect();
+
+}
+
+
+/*
+    Interaction variables of a patient, from the diagnostic group and interaction tables
+    (see `interactions`). `flags` are the age/sex variables of the patient, e.g. DISABL
+    for the institutional interactions.
+*/
+pub fn apply_interactions(definitions: &InteractionDefinitions, patient_hcc_list : &[String], flags: &[String]) -> Vec<String> {
+    return definitions.evaluate(patient_hcc_list, flags);
+}
+
+/*
+    Count variables of &ADDZ, from the number of payment HCCs left after the hierarchies:
+        ARRAY CHPYMT(&N_CC) &HCClist;
+        HCC_pymt = sum(of CHPYMT(*));
+        ZS(i) = (HCC_pymt=i);   for D1 - D9
+        D10P  = (HCC_pymt>=10);
+*/
+pub static PAYMENT_HCC_COUNT_VARIABLES: [&str; 10] = ["D1", "D2", "D3", "D4", "D5", "D6", "D7", "D8", "D9", "D10P"];
+
+/// Count variable set for `count` payment HCCs, None when the patient has none
+pub fn payment_hcc_count_variable(count: usize) -> Option<&'static str> {
+    match count {
+        0 => None,
+        1..=9 => Some(PAYMENT_HCC_COUNT_VARIABLES[count - 1]),
+        _ => Some(PAYMENT_HCC_COUNT_VARIABLES[9]),
+    }
+}
+
+
+/*
+    Score variable a beneficiary is paid on, from their eligibility segment:
+        CNA / CND   community, non dual, aged / disabled
+        CFA / CFD   community, full benefit dual, aged / disabled
+        CPA / CPD   community, partial benefit dual, aged / disabled
+        INS         long term institutional
+        NE / SNPNE  new enrollee, C-SNP new enrollee
+*/
+pub static SEGMENT_SCORES: [(&str, &str); 9] = [
+    ("CNA", "SCORE_COMMUNITY_NA"),
+    ("CND", "SCORE_COMMUNITY_ND"),
+    ("CFA", "SCORE_COMMUNITY_FBA"),
+    ("CFD", "SCORE_COMMUNITY_FBD"),
+    ("CPA", "SCORE_COMMUNITY_PBA"),
+    ("CPD", "SCORE_COMMUNITY_PBD"),
+    ("INS", "SCORE_INSTITUTIONAL"),
+    ("NE", "SCORE_NEW_ENROLLEE"),
+    ("SNPNE", "SCORE_SNP_NEW_ENROLLEE"),
+];
+
+/// Score variable of an eligibility segment, None for an unknown segment
+pub fn segment_score(eligibility_code: &str) -> Option<&'static str> {
+    SEGMENT_SCORES.iter().find(|(code, _)| *code == eligibility_code).map(|(_, score)| *score)
+}
+
+/// Count variable set over the payment HCCs of the beneficiary that are variables of the regression
+pub fn segment_count_variable(tables: &CompiledTablesView, variables: &[u32], all_raf_attributes: &[String]) -> Option<&'static str> {
+    let payment_hccs = all_raf_attributes
+        .iter()
+        .filter(|x| tables.is_payment_hcc(x))
+        .filter(|x| matches!(tables.variable_id(x), Some(id) if variables.contains(&id)))
+        .count();
+    return payment_hcc_count_variable(payment_hccs);
+}
+
+/*
+    Sums the coefficients of a %SCOREMAC call over the regression variables set for the
+    beneficiary, e.g. for SCORE_COMMUNITY_NA every variable of &COMM_REGA under CNA_.
+    Variables without a published coefficient for the prefix count as zero, as in SAS.
+
+    The count variables (D1 .. D10P) are not taken from the attributes: they are set from
+    the payment HCCs of the beneficiary that are regression variables of this segment.
+*/
+pub fn get_regression_score(tables: &CompiledTablesView, prefix: &str, variables: &[u32], all_raf_attributes: &[String]) -> f32 {
+
+    let count_variable = segment_count_variable(tables, variables, all_raf_attributes);
+
+    let mut score = 0.0;
+    for id in variables {
+        let variable = tables.variable_name(*id);
+        let is_set = if PAYMENT_HCC_COUNT_VARIABLES.contains(&variable) {
+            count_variable == Some(variable)
+        } else {
+            all_raf_attributes.iter().any(|x| x == variable)
+        };
+        if !is_set {
+            continue;
+        }
+        if let Some(coefficient) = tables.coefficient(prefix, variable) {
+            score += coefficient;
+        }
+    }
+    return score;
+}
+
+// New enrollee interactions of the age/sex cell with Medicaid and originally disabled status.
+// *Translated from V2823T2M.TXT for more details*
+//    NE_ORIGDS       = (AGEF>=65)*(OREC='1');
+//    NMCAID_NORIGDIS = (NEMCAID <=0 and NE_ORIGDS <=0);
+//    MCAID_NORIGDIS  = (NEMCAID > 0 and NE_ORIGDS <=0);
+//    NMCAID_ORIGDIS  = (NEMCAID <=0 and NE_ORIGDS > 0);
+//    MCAID_ORIGDIS   = (NEMCAID > 0 and NE_ORIGDS > 0);
+//    %INTER(PVAR =  NMCAID_NORIGDIS,  RLIST = &NE_AGESEXV );
+//    %INTER(PVAR =  MCAID_NORIGDIS,   RLIST = &NE_AGESEXV );
+//    %INTER(PVAR =  NMCAID_ORIGDIS,   RLIST = &ONE_AGESEXV);
+//    %INTER(PVAR =  MCAID_ORIGDIS,    RLIST = &ONE_AGESEXV);
+pub fn new_enrollee_cells(age_sex_attributes: &[String], age: i32, orec: &str, medicaid: bool) -> Vec<String> {
+
+    let ne_origds = age >= 65 && orec == "1";
+    let status = match (medicaid, ne_origds) {
+        (false, false) => "NMCAID_NORIGDIS",
+        (true, false) => "MCAID_NORIGDIS",
+        (false, true) => "NMCAID_ORIGDIS",
+        (true, true) => "MCAID_ORIGDIS",
+    };
+
+    // NE_ORIGDS needs AGEF >= 65, so an ORIGDIS cell is always one of &ONE_AGESEXV
+    return age_sex_attributes
+        .iter()
+        .filter(|x| x.starts_with("NEF") || x.starts_with("NEM"))
+        .map(|x| format!("{}_{}", status, x))
+        .collect();
+}
+
+#[test]
+fn can_apply_interactions() {
+    let definitions = InteractionDefinitions::v28();
+    let mut hcc_list = vec!["HCC21".to_string(), "HCC38".to_string(), "HCC221".to_string(), "HCC139".to_string()];
+    let mut flags = vec!["DISABL".to_string()];
+    let first_interactions = apply_interactions(&definitions, &hcc_list, &flags);
+
+    // CANCER_V28_DISABL should be present
+    assert!(first_interactions.contains(&"DISABLED_CANCER_V28".to_string()));
+    assert!(first_interactions.contains(&"DIABETES_HF_V28".to_string()));
+    assert!(!first_interactions.contains(&"gSubUseDisorder_gPsych_V28".to_string()));
+    // The payment HCC count is left to the scoring of each segment
+    assert!(!first_interactions.contains(&"D4".to_string()));
+
+    hcc_list = vec!["HCC21".to_string(), "HCC198".to_string(), "HCC221".to_string(), "HCC139".to_string()];
+    flags = vec![];
+    let second_interactions = apply_interactions(&definitions, &hcc_list, &flags);
+    assert!(!second_interactions.contains(&"gSubUseDisorder_gPsych_V28".to_string()));
+    assert!(!second_interactions.contains(&"DISABLED_CANCER_V28".to_string()));
+}
+
+#[test]
+fn can_build_age_sex_map() {
+    let mut age = 65;
+    let mut sex = "M";
+    let mut orec = "1";
+    let mut age_sex_map = age_sex_v2(age, sex, orec);
+
+    assert_eq!(age_sex_map.len(), 3);
+
+    assert!(age_sex_map.contains(&String::from("M65_69"))); // Should be true
+    assert!(!age_sex_map.contains(&String::from("F65_69"))); // Should be false
+    assert!(age_sex_map.contains(&String::from("NEM65"))); // Should be true
+
+    age = 64;
+    sex = "F";
+    orec = "1";
+    age_sex_map = age_sex_v2(age, sex, orec);
+
+    assert!(age_sex_map.contains(&String::from("F60_64"))); // Should be true
+    assert!(!age_sex_map.contains(&String::from("NEF65"))); // Should be false
+    assert!(age_sex_map.contains(&String::from("NEF60_64"))); // Should be true
+
+
+    orec = "0";
+    age_sex_map = age_sex_v2(age, sex, orec);
+
+    assert!(age_sex_map.contains(&String::from("NEF65"))); // Should be true
+
+
+}
+
+#[cfg(test)]
+fn _published_model() -> crate::model::ModelDefinition {
+    use crate::utils::{get_cms_data_dir, read_model_definition};
+    let path = get_cms_data_dir("PY2023");
+    read_model_definition(&[&(path.clone() + "/V2823T2P.TXT"), &(path + "/V2823T2M.TXT")]).unwrap()
+}
+
+#[cfg(test)]
+fn _score(tables: &CompiledTablesView, score: &str, all_raf_attributes: &[String]) -> f32 {
+    let (_, prefix, variables) = tables.scores().find(|x| x.0 == score).unwrap();
+    get_regression_score(tables, prefix, variables, all_raf_attributes)
+}
+
+#[test]
+fn can_generate_community_model_a_score() {
+    use crate::PublicRAFInputs;
+    use crate::compiled::CompiledRAFTables;
+
+    let all_raf_attributes: Vec<String> = vec!["M75_79".to_string(), "NEM75_79".to_string(), "DIABETES_HF_V28".to_string(), "D3".to_string()];
+
+    let mut hcc_coefficients = BTreeMap::<String, f32>::new();
+    hcc_coefficients.insert("CNA_M75_79".to_string(), 0.50);
+    hcc_coefficients.insert("CNA_NEM75_79".to_string(), 0.0);
+    hcc_coefficients.insert("CNA_DIABETES_HF_V28".to_string(), 0.11);
+    hcc_coefficients.insert("CNA_D3".to_string(), 0.0);
+    hcc_coefficients.insert("CNA_HF_HCC238_V28".to_string(), 0.08);
+    hcc_coefficients.insert("CNA_HCC379".to_string(), 1.97);
+    hcc_coefficients.insert("CNA_HCC380".to_string(), 1.08);
+    hcc_coefficients.insert("CNA_HCC381".to_string(), 1.08);
+    hcc_coefficients.insert("CNA_HCC382".to_string(), 0.84);       
+
+    let mut hcc_labels = BTreeMap::<String, String>::new(); 
+    hcc_labels.insert("HCC1".to_string(), "HIV/AIDS".to_string());
+    hcc_labels.insert("HCC2".to_string(), "Septicemia, Sepsis, Systemic Inflammatory Response Syndrome/Shock".to_string());
+    hcc_labels.insert("HCC6".to_string(), "Opportunistic Infections".to_string());
+    hcc_labels.insert("HCC17".to_string(), "Cancer Metastatic to Lung, Liver, Brain, and Other Organs; Acute Myeloid Leukemia Except Promyelocytic ".to_string());
+    hcc_labels.insert("HCC18".to_string(), "Cancer Metastatic to Bone, Other and Unspecified Metastatic Cancer; Acute Leukemia Except Myeloid ".to_string());
+    hcc_labels.insert("HCC19".to_string(), "Myelodysplastic Syndromes, Multiple Myeloma, and Other Cancers ".to_string());
+    hcc_labels.insert("HCC21".to_string(), "Lymphoma and Other Cancers ".to_string());
+    hcc_labels.insert("HCC22".to_string(), "Bladder, Colorectal, and Other Cancers ".to_string());
+    hcc_labels.insert("HCC23".to_string(), "Prostate, Breast, and Other Cancers and Tumors ".to_string());
+    hcc_labels.insert("HCC20".to_string(), "Lung and Other Severe Cancers ".to_string());
+    hcc_labels.insert("HCC35".to_string(), "Pancreas Transplant Status".to_string());
+    hcc_labels.insert("HCC36".to_string(), "Diabetes with Severe Acute Complications".to_string());
+    hcc_labels.insert("HCC37".to_string(), "Diabetes with Chronic Complications".to_string());
+    hcc_labels.insert("HCC38".to_string(), "Diabetes with Glycemic, Unspecified, or No Complications ".to_string());
+    hcc_labels.insert("HCC48".to_string(), "Morbid Obesity".to_string());
+
+    let mut hiers = BTreeMap::<String, Vec<String>>::new();
+    hiers
+        .entry("HCC17".to_string())
+        .or_insert(vec!["HCC18".to_string()]);
+
+    let mut dx_to_cc = BTreeMap::<String, Vec<String>>::new();
+    dx_to_cc
+        .entry("B20".to_string())
+        .or_insert(vec!["HCC1".to_string(), "HCC6".to_string()]);
+
+    let _public_input = PublicRAFInputs {
+        hcc_coefficients,
+        hcc_labels,
+        hcc_hierarchies: hiers,
+        dx_to_cc,
+        interactions: InteractionDefinitions::v28(),
+        model: _published_model(),
+        norm_factor: 1.0,
+    };
+
+    let _public_words = CompiledRAFTables::compile(&_public_input).unwrap().to_words();
+    let _public_tables = CompiledTablesView::new(&_public_words).unwrap();
+
+    // NEM75_79 is not a variable of &COMM_REGA
+    let score_community_na = _score(&_public_tables, "SCORE_COMMUNITY_NA", &all_raf_attributes);
+
+    assert_eq!(score_community_na, 0.61);
+
+}
+
+#[test]
+fn can_build_new_enrollee_cells() {
+    let cells = |age: i32, sex: &str, orec: &str, medicaid: bool| {
+        new_enrollee_cells(&age_sex_v2(age, sex, orec), age, orec, medicaid)
+    };
+
+    assert_eq!(cells(70, "F", "0", false), vec!["NMCAID_NORIGDIS_NEF70_74".to_string()]);
+    assert_eq!(cells(70, "M", "0", true), vec!["MCAID_NORIGDIS_NEM70_74".to_string()]);
+    assert_eq!(cells(67, "F", "1", false), vec!["NMCAID_ORIGDIS_NEF67".to_string()]);
+    assert_eq!(cells(66, "M", "1", true), vec!["MCAID_ORIGDIS_NEM66".to_string()]);
+
+    // Under 65 a beneficiary is never originally disabled for the new enrollee model
+    assert_eq!(cells(40, "F", "1", true), vec!["MCAID_NORIGDIS_NEF35_44".to_string()]);
+
+    // Age 64 with OREC 0 falls in the 65 cell
+    assert_eq!(cells(64, "M", "0", false), vec!["NMCAID_NORIGDIS_NEM65".to_string()]);
+}
+
+#[test]
+fn can_generate_new_enrollee_score() {
+    use crate::PublicRAFInputs;
+    use crate::compiled::CompiledRAFTables;
+
+    let mut hcc_coefficients = BTreeMap::<String, f32>::new();
+    hcc_coefficients.insert("NE_MCAID_ORIGDIS_NEM66".to_string(), 1.25);
+    hcc_coefficients.insert("SNPNE_MCAID_ORIGDIS_NEM66".to_string(), 1.75);
+    hcc_coefficients.insert("NE_NMCAID_NORIGDIS_NEM66".to_string(), 0.5);
+    let mut hcc_labels = BTreeMap::<String, String>::new();
+    hcc_labels.insert("HCC1".to_string(), "HIV/AIDS".to_string());
+
+    let _public_input = PublicRAFInputs {
+        hcc_coefficients,
+        hcc_labels,
+        hcc_hierarchies: BTreeMap::new(),
+        dx_to_cc: BTreeMap::new(),
+        interactions: InteractionDefinitions::v28(),
+        model: _published_model(),
+        norm_factor: 1.0,
+    };
+    let _public_words = CompiledRAFTables::compile(&_public_input).unwrap().to_words();
+    let _public_tables = CompiledTablesView::new(&_public_words).unwrap();
+
+    let mut all_raf_attributes = age_sex_v2(66, "M", "1");
+    all_raf_attributes.extend(new_enrollee_cells(&all_raf_attributes, 66, "1", true));
+
+    assert_eq!(_score(&_public_tables, "SCORE_NEW_ENROLLEE", &all_raf_attributes), 1.25);
+    assert_eq!(_score(&_public_tables, "SCORE_SNP_NEW_ENROLLEE", &all_raf_attributes), 1.75);
+}
+
+#[test]
+fn counts_payment_hccs_per_segment() {
+    use crate::PublicRAFInputs;
+    use crate::compiled::CompiledRAFTables;
+    use crate::utils::parse_model_definition;
+
+    // HCC1 .. HCC11 are payment HCCs, HCC12 is not; the institutional list only has HCC1 .. HCC5
+    let hccs: Vec<String> = (1..=12).map(|x| format!("HCC{}", x)).collect();
+    let sas = format!("
+        %LET HCClist=%STR({});
+        %LET ADDZ=%STR(D1 D2 D3 D4 D5 D6 D7 D8 D9 D10P);
+        %LET COMM_REGA=%STR(&HCClist HCC12 &ADDZ);
+        %LET INST_REG=%STR(HCC1 HCC2 HCC3 HCC4 HCC5 &ADDZ);
+        %&SCOREMAC(PVAR=SCORE_COMMUNITY_NA, RLIST=&COMM_REGA, CPREF=CNA_);
+        %&SCOREMAC(PVAR=SCORE_INSTITUTIONAL, RLIST=&INST_REG, CPREF=INS_);", hccs[..11].join(" "));
+
+    // HCCs score nothing, Dn scores n, so each score is the count variable in effect
+    let mut hcc_coefficients = BTreeMap::<String, f32>::new();
+    let mut hcc_labels = BTreeMap::<String, String>::new();
+    for prefix in ["CNA", "INS"] {
+        for hcc in &hccs {
+            hcc_coefficients.insert(format!("{}_{}", prefix, hcc), 0.0);
+        }
+        for (i, count_variable) in PAYMENT_HCC_COUNT_VARIABLES.iter().enumerate() {
+            hcc_coefficients.insert(format!("{}_{}", prefix, count_variable), (i + 1) as f32);
+        }
+    }
+    for hcc in &hccs {
+        hcc_labels.insert(hcc.clone(), format!("{} label", hcc));
+    }
+
+    let _public_input = PublicRAFInputs {
+        hcc_coefficients,
+        hcc_labels,
+        hcc_hierarchies: BTreeMap::new(),
+        dx_to_cc: BTreeMap::new(),
+        interactions: InteractionDefinitions::default(),
+        model: parse_model_definition(&[("V2823T2M.TXT", &sas)]).unwrap(),
+        norm_factor: 1.0,
+    };
+    let _public_words = CompiledRAFTables::compile(&_public_input).unwrap().to_words();
+    let _public_tables = CompiledTablesView::new(&_public_words).unwrap();
+
+    let scores = |n: usize| {
+        let attributes: Vec<String> = hccs[..n].to_vec();
+        (_score(&_public_tables, "SCORE_COMMUNITY_NA", &attributes), _score(&_public_tables, "SCORE_INSTITUTIONAL", &attributes))
+    };
+    assert_eq!(scores(0), (0.0, 0.0));
+    assert_eq!(scores(1), (1.0, 1.0));
+    assert_eq!(scores(9), (9.0, 5.0));
+    assert_eq!(scores(10), (10.0, 5.0));
+    // HCC12 is a regression variable but not a payment HCC, so it is not counted
+    assert_eq!(scores(12), (10.0, 5.0));
+    assert_eq!(_score(&_public_tables, "SCORE_COMMUNITY_NA", &hccs[11..]), 0.0);
+
+    // A count variable in the attributes does not override the count of the segment
+    let attributes = vec!["HCC1".to_string(), "D3".to_string()];
+    assert_eq!(_score(&_public_tables, "SCORE_COMMUNITY_NA", &attributes), 1.0);
+
+    assert_eq!(payment_hcc_count_variable(0), None);
+    assert_eq!(payment_hcc_count_variable(1), Some("D1"));
+    assert_eq!(payment_hcc_count_variable(9), Some("D9"));
+    assert_eq!(payment_hcc_count_variable(10), Some("D10P"));
+    assert_eq!(payment_hcc_count_variable(27), Some("D10P"));
+}
+
+#[test]
+fn can_score_beneficiary_natively() {
+    use crate::compiled::CompiledRAFTables;
+    use crate::utils::{get_cms_data_dir, load_public_inputs};
+
+    let public_inputs = load_public_inputs(&get_cms_data_dir("PY2023")).unwrap();
+    let words = CompiledRAFTables::compile(&public_inputs).unwrap().to_words();
+    let context = ScoringContext::new(CompiledTablesView::new(&words).unwrap(), &public_inputs.interactions);
+
+    let private_input = PrivateRAFInput {
+        diagnosis_codes: vec!["E1122".to_string(), "E119".to_string(), "I5020".to_string()],
+        age: 70,
+        sex: "M".to_string(),
+        eligibility_code: "CNA".to_string(),
+        entitlement_reason_code: "0".to_string(),
+        medicaid_status: false,
+        long_term_institutionalized: false,
+    };
+    let mapped_hccs: Vec<String> = private_input
+        .diagnosis_codes
+        .iter()
+        .flat_map(|dx| public_inputs.dx_to_cc[dx].iter().cloned())
+        .collect();
+
+    let scores = context.score(&private_input, &mapped_hccs);
+
+    // HCC37 zeroes HCC38; diabetes with heart failure interacts, 2 payment HCCs
+    assert_eq!(scores.hccs, vec!["HCC37".to_string(), "HCC226".to_string()]);
+    assert!(scores.attributes.contains(&"DIABETES_HF_V28".to_string()));
+    assert!(!scores.attributes.contains(&"D2".to_string()));
+    assert_eq!(scores.count_variables["SCORE_COMMUNITY_NA"], "D2");
+    assert_eq!(scores.count_variables["SCORE_INSTITUTIONAL"], "D2");
+    assert_eq!(scores.attributes.iter().filter(|x| *x == "HCC37").count(), 1);
+
+    // CNA_M70_74 + CNA_HCC37 + CNA_HCC226 + CNA_DIABETES_HF_V28 + CNA_D2
+    assert!((scores.raf_scores["SCORE_COMMUNITY_NA"] - (0.396 + 0.166 + 0.36 + 0.112)).abs() < 1e-5);
+    // INS_M70_74 + INS_HCC37 + INS_HCC226 + INS_DIABETES_HF_V28 + INS_D2
+    assert!((scores.raf_scores["SCORE_INSTITUTIONAL"] - (1.224 + 0.28 + 0.217 + 0.209)).abs() < 1e-5);
+    assert!((scores.raf_scores["SCORE_NEW_ENROLLEE"] - 0.808).abs() < 1e-5);
+}
+
+#[test]
+fn scores_originally_disabled_and_substance_use_in_their_segments() {
+    use crate::compiled::CompiledRAFTables;
+    use crate::utils::{get_cms_data_dir, load_public_inputs};
+
+    // &COMM_REGA has &orig_int but not gSubUseDisorder_gPsych_V28, which is only in &COMM_REGD
+    let public_inputs = load_public_inputs(&get_cms_data_dir("PY2023")).unwrap();
+    let comm_rega = &public_inputs.model.score("SCORE_COMMUNITY_NA").unwrap().variables;
+    assert!(comm_rega.contains(&"OriginallyDisabled_Female".to_string()));
+    assert!(comm_rega.contains(&"OriginallyDisabled_Male".to_string()));
+    assert!(!comm_rega.contains(&"gSubUseDisorder_gPsych_V28".to_string()));
+
+    let words = CompiledRAFTables::compile(&public_inputs).unwrap().to_words();
+    let context = ScoringContext::new(CompiledTablesView::new(&words).unwrap(), &public_inputs.interactions);
+    let mapped_hccs = vec!["HCC137".to_string(), "HCC155".to_string()];
+    let member = |age: i32| PrivateRAFInput {
+        diagnosis_codes: vec![],
+        age,
+        sex: "F".to_string(),
+        eligibility_code: "CNA".to_string(),
+        entitlement_reason_code: "1".to_string(),
+        medicaid_status: false,
+        long_term_institutionalized: false,
+    };
+
+    // Aged and originally disabled: CNA_F65_69 + CNA_OriginallyDisabled_Female + CNA_HCC137 + CNA_HCC155
+    let aged = context.score(&member(68), &mapped_hccs);
+    assert!(aged.attributes.contains(&"gSubUseDisorder_gPsych_V28".to_string()));
+    assert!((aged.raf_scores["SCORE_COMMUNITY_NA"] - (0.33 + 0.228 + 0.424 + 0.299)).abs() < 1e-5);
+
+    // Disabled: CND_F60_64 + CND_HCC137 + CND_HCC155 + CND_gSubUseDisorder_gPsych_V28
+    let disabled = context.score(&member(62), &mapped_hccs);
+    assert!(!disabled.attributes.contains(&"OriginallyDisabled_Female".to_string()));
+    assert!((disabled.raf_scores["SCORE_COMMUNITY_ND"] - (0.436 + 0.365 + 0.166 + 0.087)).abs() < 1e-5);
+}
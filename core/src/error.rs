@@ -0,0 +1,83 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Errors raised while loading the CMS data files used to build `PublicRAFInputs`.
+/// Every variant carries the path of the offending file and, where it is known,
+/// the 1-based line number so a bad table can be located without a debugger.
+#[derive(Debug)]
+pub enum RAFDataError {
+    // The file could not be opened
+    Open { path: String, source: io::Error },
+
+    // The file was opened but a line could not be read
+    Read { path: String, line: usize, source: io::Error },
+
+    // The delimited reader rejected a record
+    Csv { path: String, line: Option<u64>, source: csv::Error },
+
+    // A line was read but did not have the shape the loader expected
+    Parse { path: String, line: usize, message: String },
+
+    // The file was read successfully but did not contain a single usable entry
+    Empty { path: String },
+}
+
+impl RAFDataError {
+    /// Path of the file that failed to load
+    pub fn path(&self) -> &str {
+        match self {
+            RAFDataError::Open { path, .. } => path,
+            RAFDataError::Read { path, .. } => path,
+            RAFDataError::Csv { path, .. } => path,
+            RAFDataError::Parse { path, .. } => path,
+            RAFDataError::Empty { path } => path,
+        }
+    }
+
+    /// Line number of the failure, when it can be attributed to a single line
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            RAFDataError::Read { line, .. } => Some(*line),
+            RAFDataError::Csv { line, .. } => line.map(|l| l as usize),
+            RAFDataError::Parse { line, .. } => Some(*line),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RAFDataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RAFDataError::Open { path, source } => {
+                write!(f, "{}: unable to open file: {}", path, source)
+            }
+            RAFDataError::Read { path, line, source } => {
+                write!(f, "{}:{}: unable to read line: {}", path, line, source)
+            }
+            RAFDataError::Csv { path, line: Some(line), source } => {
+                write!(f, "{}:{}: malformed record: {}", path, line, source)
+            }
+            RAFDataError::Csv { path, line: None, source } => {
+                write!(f, "{}: malformed record: {}", path, source)
+            }
+            RAFDataError::Parse { path, line, message } => {
+                write!(f, "{}:{}: {}", path, line, message)
+            }
+            RAFDataError::Empty { path } => {
+                write!(f, "{}: file does not contain any entries", path)
+            }
+        }
+    }
+}
+
+impl Error for RAFDataError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RAFDataError::Open { source, .. } => Some(source),
+            RAFDataError::Read { source, .. } => Some(source),
+            RAFDataError::Csv { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
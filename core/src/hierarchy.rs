@@ -0,0 +1,138 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// HCC hierarchies of the V28 model, applied the way the CMS SAS software does.
+//
+// V2823T2M first applies the heart interaction patch to the CCs, then V28115H1 copies
+// every CC into its HCC and runs one %SET0 per superior HCC, in the order they are
+// listed, zeroing the inferior HCCs when the superior one is still set:
+//
+//    %MACRO SET0( CC=, HIER= );
+//    IF HCC&CC=1 THEN DO; HCC%SCAN(&HIER,&K) = 0; ... END;
+//
+// Because each %SET0 reads the HCCs left by the previous ones, a superior HCC that was
+// itself zeroed earlier no longer zeroes its own inferiors (e.g. HCC62 zeroes HCC63, so
+// HCC63 does not zero HCC202). HCCs are identified by their number (HCC17 -> 17).
+
+/*
+    Heart interaction patch applied to the CCs before the hierarchies:
+        IF CC223 = 1 AND MAX(CC221, CC222, CC224, CC225, CC226) = 0 THEN CC223 = 0;
+*/
+pub static HEART_PATCH_CC: u32 = 223;
+pub static HEART_PATCH_REQUIRES: [u32; 5] = [221, 222, 224, 225, 226];
+
+/// Number of an HCC or CC name, e.g. "HCC17" or "CC17" -> 17
+pub fn hcc_number(name: &str) -> Option<u32> {
+    let digits = name.strip_prefix("HCC").or_else(|| name.strip_prefix("CC"))?;
+    digits.parse::<u32>().ok()
+}
+
+/*
+    Orders the hierarchies into %SET0 statements. V28115H1 lists its statements by
+    ascending superior HCC number, which is the order reproduced here since the
+    hierarchy table itself is keyed by name. Fails with the name of the first HCC that
+    has no number.
+*/
+pub fn set0_statements(hiers: &BTreeMap<String, Vec<String>>) -> Result<Vec<(u32, Vec<u32>)>, String> {
+
+    let number = |name: &String| hcc_number(name).ok_or_else(|| name.clone());
+    let mut statements = vec![];
+    for (superior, inferiors) in hiers {
+        let inferiors = inferiors.iter().map(number).collect::<Result<Vec<u32>, String>>()?;
+        statements.push((number(superior)?, inferiors));
+    }
+    statements.sort_by_key(|x| x.0);
+    Ok(statements)
+}
+
+/*
+    Applies the heart patch and the %SET0 statements to the CCs of a patient and returns
+    the HCCs left set. Every CC becomes an HCC unless a superior HCC zeroes it, so HCCs
+    without a hierarchy of their own are kept, and the order of `ccs` does not matter.
+*/
+pub fn apply_hierarchies<'a, I>(ccs: &BTreeSet<u32>, set0: I) -> BTreeSet<u32>
+where
+    I: IntoIterator<Item = (u32, &'a [u32])>,
+{
+    let mut hccs = ccs.clone();
+    if !HEART_PATCH_REQUIRES.iter().any(|cc| ccs.contains(cc)) {
+        hccs.remove(&HEART_PATCH_CC);
+    }
+
+    for (superior, inferiors) in set0 {
+        if hccs.contains(&superior) {
+            for inferior in inferiors {
+                hccs.remove(inferior);
+            }
+        }
+    }
+    return hccs;
+}
+
+#[cfg(test)]
+fn _published_set0_statements() -> Vec<(u32, Vec<u32>)> {
+    use crate::utils::{get_cms_data_dir, read_hier};
+    let hiers = read_hier(&(get_cms_data_dir("PY2023") + "/V28115H1.TXT")).unwrap();
+    set0_statements(&hiers).unwrap()
+}
+
+#[cfg(test)]
+fn _apply(ccs: &[u32], set0: &[(u32, Vec<u32>)]) -> Vec<u32> {
+    let ccs: BTreeSet<u32> = ccs.iter().copied().collect();
+    apply_hierarchies(&ccs, set0.iter().map(|(x, y)| (*x, y.as_slice()))).into_iter().collect()
+}
+
+#[test]
+fn can_apply_hierarchy() {
+    let set0 = vec![
+        (17, vec![18, 19, 20, 21, 22, 23]),
+        (154, vec![155]),
+        (180, vec![181, 182, 253, 254]),
+        (222, vec![223, 224, 225, 226, 227]),
+    ];
+    let hccs = _apply(&[154, 155, 17, 19], &set0);
+    assert!(hccs.contains(&154));
+    assert!(!hccs.contains(&155));
+    assert!(hccs.contains(&17));
+    assert!(!hccs.contains(&19));
+    assert_eq!(hcc_number("HCC154"), Some(154));
+    assert_eq!(hcc_number("CC23"), Some(23));
+    assert_eq!(hcc_number("D10P"), None);
+}
+
+#[test]
+fn set0_order_matches_v28115h1() {
+    // The statements in the SAS macro, in the order they are listed
+    let text = std::fs::read_to_string(crate::utils::get_cms_data_dir("PY2023") + "/V28115H1.TXT").unwrap();
+    let listed: Vec<u32> = regex::Regex::new(r"%SET0\(CC=(\d+)")
+        .unwrap()
+        .captures_iter(&text)
+        .map(|x| x[1].parse().unwrap())
+        .collect();
+    let ordered: Vec<u32> = _published_set0_statements().iter().map(|x| x.0).collect();
+    assert_eq!(ordered, listed);
+}
+
+/*
+    Golden cases in fixtures/v28_hierarchy_cases.txt, one per line:
+        <CCs> ; <expected HCCs> ; <description>
+    One case per %SET0 row of the CMS published V28115H1, then hand-derived cases for the
+    interactions between rows, all run against the published hierarchies.
+*/
+#[test]
+fn matches_v28_hierarchy_golden_cases() {
+    let set0 = _published_set0_statements();
+    let fixture = include_str!("../fixtures/v28_hierarchy_cases.txt");
+    let parse = |x: &str| -> Vec<u32> { x.split_whitespace().map(|x| x.parse().unwrap()).collect() };
+
+    let mut cases = 0;
+    for line in fixture.lines().map(|x| x.trim()).filter(|x| !x.is_empty() && !x.starts_with('#')) {
+        let fields: Vec<&str> = line.split(';').collect();
+        let ccs = parse(fields[0]);
+        assert_eq!(_apply(&ccs, &set0), parse(fields[1]), "{}", fields[2].trim());
+        cases += 1;
+    }
+    assert!(cases >= 90);
+}
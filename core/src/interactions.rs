@@ -0,0 +1,157 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// Diagnostic groups and interaction variables of the V28 CMS-HCC model, expressed as data.
+// *Translated from V2823T2M.TXT for more details*
+//
+// A diagnostic group is set when any of its member HCCs is set (SAS `MAX(...)`), and an
+// interaction is the product of its factors, each of which is a diagnostic group, a model
+// HCC or a beneficiary flag such as DISABL. The definitions travel with `PublicRAFInputs`
+// and are committed in the `Journal`, so a verifier sees exactly which ones were proven.
+
+/*
+Diagnostic categories:
+    CANCER_V28          = MAX(HCC17, HCC18, HCC19, HCC20, HCC21, HCC22, HCC23);
+    DIABETES_V28        = MAX(HCC35, HCC36, HCC37, HCC38);
+    CARD_RESP_FAIL      = MAX(HCC211, HCC212, HCC213);
+    HF_V28              = MAX(HCC221, HCC222, HCC223, HCC224, HCC225, HCC226);
+    CHR_LUNG_V28        = MAX(HCC276, HCC277, HCC278, HCC279, HCC280);
+    KIDNEY_V28          = MAX(HCC326, HCC327, HCC328, HCC329);
+    SEPSIS              = HCC2;
+    gSubUseDisorder_V28 = MAX(HCC135, HCC136, HCC137, HCC138, HCC139);
+    gPsychiatric_V28    = MAX(HCC151, HCC152, HCC153, HCC154, HCC155);
+    NEURO_V28           = MAX(HCC180, HCC181, HCC182, HCC190, HCC191, HCC192, HCC195, HCC196, HCC198, HCC199);
+    ULCER_V28           = MAX(HCC379, HCC380, HCC381, HCC382);
+*/
+pub static V28_DIAGNOSTIC_GROUPS: [(&str, &[&str]); 11] = [
+    ("CANCER_V28", &["HCC17", "HCC18", "HCC19", "HCC20", "HCC21", "HCC22", "HCC23"]),
+    ("DIABETES_V28", &["HCC35", "HCC36", "HCC37", "HCC38"]),
+    ("CARD_RESP_FAIL", &["HCC211", "HCC212", "HCC213"]),
+    ("HF_V28", &["HCC221", "HCC222", "HCC223", "HCC224", "HCC225", "HCC226"]),
+    ("CHR_LUNG_V28", &["HCC276", "HCC277", "HCC278", "HCC279", "HCC280"]),
+    ("KIDNEY_V28", &["HCC326", "HCC327", "HCC328", "HCC329"]),
+    ("SEPSIS", &["HCC2"]),
+    ("gSubUseDisorder_V28", &["HCC135", "HCC136", "HCC137", "HCC138", "HCC139"]),
+    ("gPsychiatric_V28", &["HCC151", "HCC152", "HCC153", "HCC154", "HCC155"]),
+    ("NEURO_V28", &["HCC180", "HCC181", "HCC182", "HCC190", "HCC191", "HCC192", "HCC195", "HCC196", "HCC198", "HCC199"]),
+    ("ULCER_V28", &["HCC379", "HCC380", "HCC381", "HCC382"]),
+];
+
+/*
+Community models interactions:
+    DIABETES_HF_V28               = DIABETES_V28*HF_V28;
+    HF_CHR_LUNG_V28               = HF_V28*CHR_LUNG_V28;
+    HF_KIDNEY_V28                 = HF_V28*KIDNEY_V28;
+    CHR_LUNG_CARD_RESP_FAIL_V28   = CHR_LUNG_V28*CARD_RESP_FAIL;
+    HF_HCC238_V28                 = HF_V28*HCC238;
+    gSubUseDisorder_gPsych_V28    = gSubUseDisorder_V28*gPsychiatric_V28;
+
+Institutional model:
+    DISABLED_CANCER_V28          = DISABL*CANCER_V28;
+    DISABLED_NEURO_V28           = DISABL*NEURO_V28;
+    DISABLED_HF_V28              = DISABL*HF_V28;
+    DISABLED_CHR_LUNG_V28        = DISABL*CHR_LUNG_V28;
+    DISABLED_ULCER_V28           = DISABL*ULCER_V28;
+*/
+pub static V28_INTERACTIONS: [(&str, &[&str]); 11] = [
+    ("DIABETES_HF_V28", &["DIABETES_V28", "HF_V28"]),
+    ("HF_CHR_LUNG_V28", &["HF_V28", "CHR_LUNG_V28"]),
+    ("HF_KIDNEY_V28", &["HF_V28", "KIDNEY_V28"]),
+    ("CHR_LUNG_CARD_RESP_FAIL_V28", &["CHR_LUNG_V28", "CARD_RESP_FAIL"]),
+    ("HF_HCC238_V28", &["HF_V28", "HCC238"]),
+    ("gSubUseDisorder_gPsych_V28", &["gSubUseDisorder_V28", "gPsychiatric_V28"]),
+    ("DISABLED_CANCER_V28", &["DISABL", "CANCER_V28"]),
+    ("DISABLED_NEURO_V28", &["DISABL", "NEURO_V28"]),
+    ("DISABLED_HF_V28", &["DISABL", "HF_V28"]),
+    ("DISABLED_CHR_LUNG_V28", &["DISABL", "CHR_LUNG_V28"]),
+    ("DISABLED_ULCER_V28", &["DISABL", "ULCER_V28"]),
+];
+
+// Beneficiary flags an interaction may use as a factor, set by the age/sex macro
+pub static INTERACTION_FLAGS: [&str; 2] = ["DISABL", "ORIGDS"];
+
+/// Diagnostic groups and the interactions built from them
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InteractionDefinitions {
+    // Diagnostic group name -> member HCCs, set when any member is set
+    pub groups: BTreeMap<String, Vec<String>>,
+
+    // Interaction variable -> factors (groups, HCCs or flags) that must all be set
+    pub interactions: BTreeMap<String, Vec<String>>,
+}
+
+impl InteractionDefinitions {
+
+    /// The diagnostic groups and interactions published for V28
+    pub fn v28() -> Self {
+        let to_map = |table: &[(&str, &[&str])]| -> BTreeMap<String, Vec<String>> {
+            table
+                .iter()
+                .map(|(name, members)| (name.to_string(), members.iter().map(|x| x.to_string()).collect()))
+                .collect()
+        };
+        InteractionDefinitions {
+            groups: to_map(&V28_DIAGNOSTIC_GROUPS),
+            interactions: to_map(&V28_INTERACTIONS),
+        }
+    }
+
+    /// Diagnostic groups set by the HCCs of a patient
+    pub fn groups_set(&self, hccs: &[String]) -> BTreeSet<&str> {
+        self.groups
+            .iter()
+            .filter(|(_, members)| members.iter().any(|x| hccs.contains(x)))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /*
+        Interaction variables set for a patient: every factor has to be a diagnostic group
+        set by `hccs`, one of the `hccs` itself, or one of the `flags` (e.g. DISABL).
+    */
+    pub fn evaluate(&self, hccs: &[String], flags: &[String]) -> Vec<String> {
+
+        let groups = self.groups_set(hccs);
+        let is_set = |factor: &String| {
+            groups.contains(factor.as_str()) || hccs.contains(factor) || flags.contains(factor)
+        };
+        return self
+            .interactions
+            .iter()
+            .filter(|(_, factors)| !factors.is_empty() && factors.iter().all(is_set))
+            .map(|(name, _)| name.to_string())
+            .collect();
+    }
+}
+
+#[cfg(test)]
+fn _strings(names: &[&str]) -> Vec<String> {
+    names.iter().map(|x| x.to_string()).collect()
+}
+
+#[test]
+fn can_evaluate_v28_interactions() {
+    let definitions = InteractionDefinitions::v28();
+    assert_eq!(definitions.groups.len(), 11);
+    assert_eq!(definitions.interactions.len(), 11);
+
+    let hccs = _strings(&["HCC21", "HCC38", "HCC221", "HCC238"]);
+    assert_eq!(definitions.groups_set(&hccs).into_iter().collect::<Vec<_>>(), vec!["CANCER_V28", "DIABETES_V28", "HF_V28"]);
+    assert_eq!(definitions.evaluate(&hccs, &_strings(&["DISABL"])),
+               _strings(&["DIABETES_HF_V28", "DISABLED_CANCER_V28", "DISABLED_HF_V28", "HF_HCC238_V28"]));
+    assert_eq!(definitions.evaluate(&hccs, &[]), _strings(&["DIABETES_HF_V28", "HF_HCC238_V28"]));
+}
+
+#[test]
+fn can_evaluate_custom_interactions() {
+    // A correction only needs new tables, e.g. moving HCC139 out of the substance use group
+    let mut definitions = InteractionDefinitions::v28();
+    definitions.groups.get_mut("gSubUseDisorder_V28").unwrap().retain(|x| x != "HCC139");
+    definitions.interactions.insert("ORIGDS_SEPSIS".to_string(), _strings(&["ORIGDS", "SEPSIS"]));
+
+    let hccs = _strings(&["HCC139", "HCC151", "HCC2"]);
+    assert_eq!(definitions.evaluate(&hccs, &_strings(&["ORIGDS"])), _strings(&["ORIGDS_SEPSIS"]));
+    assert_eq!(InteractionDefinitions::v28().evaluate(&hccs, &[]), _strings(&["gSubUseDisorder_gPsych_V28"]));
+}
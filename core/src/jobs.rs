@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Range;
+use crate::chunks::chunk_ranges;
+use crate::compiled::{interactions_digest, tables_digest};
+use crate::dx_table::DxTableCommitment;
+use crate::interactions::InteractionDefinitions;
+use crate::merkle::{Hash, MerkleHasher};
+use crate::PrivateRAFInput;
+
+// Manifest of a resumable proving job.
+//
+// A job directory holds a `JobManifest` (job.json) and, per proven chunk, the receipts and
+// journals of its members. The Host writes the chunk files first and only then marks the
+// chunk proven in the manifest, so after a crash every chunk marked proven is complete and
+// the others are proven again. The manifest records digests of everything the receipts
+// depend on; a job is only resumed when the inputs given again produce the same `JobInputs`.
+
+// Version of the job directory layout
+pub const JOB_FORMAT_VERSION: u32 = 1;
+
+/// Digests of the inputs a job proves, compared field by field on resume
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobInputs {
+    // Image ID of the Guest the receipts are proven for
+    pub image_id: [u32; 8],
+
+    // SHA-256 of the compiled public tables, see `compiled::tables_digest`
+    pub tables_digest: Hash,
+
+    pub dx_table: DxTableCommitment,
+
+    // SHA-256 of the bincode encoding of the interaction definitions
+    pub interactions_digest: Hash,
+
+    // SHA-256 of the bincode encoding of the roster, in roster order
+    pub roster_digest: Hash,
+
+    pub members: u32,
+}
+
+impl JobInputs {
+    pub fn new<H: MerkleHasher>(
+        image_id: [u32; 8],
+        table_words: &[u32],
+        dx_table: &DxTableCommitment,
+        interactions: &InteractionDefinitions,
+        roster: &[PrivateRAFInput],
+    ) -> Self {
+        JobInputs {
+            image_id,
+            tables_digest: tables_digest::<H>(table_words),
+            dx_table: *dx_table,
+            interactions_digest: interactions_digest::<H>(interactions),
+            roster_digest: H::sha256(&bincode::serialize(roster).unwrap()),
+            members: roster.len() as u32,
+        }
+    }
+
+    // Names of the fields that differ from `other`
+    fn differences(&self, other: &JobInputs) -> Vec<&'static str> {
+        let mut fields = vec![];
+        if self.image_id != other.image_id {
+            fields.push("image_id");
+        }
+        if self.tables_digest != other.tables_digest {
+            fields.push("tables_digest");
+        }
+        if self.dx_table != other.dx_table {
+            fields.push("dx_table");
+        }
+        if self.interactions_digest != other.interactions_digest {
+            fields.push("interactions_digest");
+        }
+        if self.roster_digest != other.roster_digest {
+            fields.push("roster_digest");
+        }
+        if self.members != other.members {
+            fields.push("members");
+        }
+        return fields;
+    }
+}
+
+/// A chunk of consecutive members and whether its receipts are on disk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkStatus {
+    pub start: u32,
+    pub end: u32,
+    pub proven: bool,
+}
+
+impl ChunkStatus {
+    pub fn members(&self) -> Range<usize> {
+        self.start as usize..self.end as usize
+    }
+}
+
+/// Contents of job.json
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobManifest {
+    pub version: u32,
+    pub inputs: JobInputs,
+    pub chunk_size: u32,
+    pub chunks: Vec<ChunkStatus>,
+}
+
+/// Reasons a job directory cannot be resumed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobError {
+    // Written by another version of the job layout
+    UnsupportedVersion(u32),
+
+    // The inputs given to resume are not the ones the job was started with
+    InputsChanged(Vec<&'static str>),
+
+    // A chunk index past the end of the job
+    UnknownChunk(usize),
+}
+
+impl fmt::Display for JobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobError::UnsupportedVersion(version) => {
+                write!(f, "job format version {} is not supported, expected {}", version, JOB_FORMAT_VERSION)
+            }
+            JobError::InputsChanged(fields) => write!(f, "inputs differ from the job: {}", fields.join(", ")),
+            JobError::UnknownChunk(chunk) => write!(f, "job has no chunk {}", chunk),
+        }
+    }
+}
+
+impl JobManifest {
+    pub fn new(inputs: JobInputs, chunk_size: usize) -> Self {
+        let chunks = chunk_ranges(inputs.members as usize, chunk_size)
+            .into_iter()
+            .map(|x| ChunkStatus { start: x.start as u32, end: x.end as u32, proven: false })
+            .collect();
+        JobManifest { version: JOB_FORMAT_VERSION, inputs, chunk_size: chunk_size.max(1) as u32, chunks }
+    }
+
+    /// Checks a manifest read from disk can be resumed with `inputs`
+    pub fn check_resume(&self, inputs: &JobInputs) -> Result<(), JobError> {
+        if self.version != JOB_FORMAT_VERSION {
+            return Err(JobError::UnsupportedVersion(self.version));
+        }
+        let fields = self.inputs.differences(inputs);
+        if !fields.is_empty() {
+            return Err(JobError::InputsChanged(fields));
+        }
+        Ok(())
+    }
+
+    /// Indexes of the chunks still to prove
+    pub fn pending(&self) -> Vec<usize> {
+        (0..self.chunks.len()).filter(|x| !self.chunks[*x].proven).collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.chunks.iter().all(|x| x.proven)
+    }
+
+    pub fn mark_proven(&mut self, chunk: usize) -> Result<(), JobError> {
+        let status = self.chunks.get_mut(chunk).ok_or(JobError::UnknownChunk(chunk))?;
+        status.proven = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn _job_inputs(roster: &[PrivateRAFInput]) -> JobInputs {
+    use crate::merkle::Sha2Hasher;
+
+    let dx_table = DxTableCommitment { root: [3; 32], rows: 10 };
+    JobInputs::new::<Sha2Hasher>([1; 8], &[1, 2, 3], &dx_table, &InteractionDefinitions::default(), roster)
+}
+
+#[test]
+fn can_track_proven_chunks() {
+    let roster: Vec<PrivateRAFInput> = (0..7)
+        .map(|age| PrivateRAFInput {
+            diagnosis_codes: vec!["E119".to_string()],
+            age: 65 + age,
+            sex: "F".to_string(),
+            eligibility_code: "CNA".to_string(),
+            entitlement_reason_code: "0".to_string(),
+            medicaid_status: false,
+            long_term_institutionalized: false,
+        })
+        .collect();
+    let mut manifest = JobManifest::new(_job_inputs(&roster), 3);
+    assert_eq!(manifest.chunks.iter().map(|x| x.members()).collect::<Vec<_>>(), vec![0..3, 3..6, 6..7]);
+    assert_eq!(manifest.pending(), vec![0, 1, 2]);
+
+    manifest.mark_proven(1).unwrap();
+    assert_eq!(manifest.pending(), vec![0, 2]);
+    assert_eq!(manifest.mark_proven(3), Err(JobError::UnknownChunk(3)));
+    manifest.mark_proven(0).unwrap();
+    manifest.mark_proven(2).unwrap();
+    assert!(manifest.is_complete());
+}
+
+#[test]
+fn refuses_to_resume_with_other_inputs() {
+    let member = PrivateRAFInput {
+        diagnosis_codes: vec!["E119".to_string()],
+        age: 70,
+        sex: "M".to_string(),
+        eligibility_code: "CNA".to_string(),
+        entitlement_reason_code: "0".to_string(),
+        medicaid_status: false,
+        long_term_institutionalized: false,
+    };
+    let roster = vec![member.clone(), member.clone()];
+    let manifest = JobManifest::new(_job_inputs(&roster), 1);
+    assert_eq!(manifest.check_resume(&_job_inputs(&roster)), Ok(()));
+
+    let mut changed = roster.clone();
+    changed[1].diagnosis_codes.push("I5020".to_string());
+    assert_eq!(manifest.check_resume(&_job_inputs(&changed)), Err(JobError::InputsChanged(vec!["roster_digest"])));
+    let mut other_guest = _job_inputs(&roster);
+    other_guest.image_id = [2; 8];
+    assert_eq!(manifest.check_resume(&other_guest), Err(JobError::InputsChanged(vec!["image_id"])));
+    assert_eq!(manifest.check_resume(&_job_inputs(&roster[..1])), Err(JobError::InputsChanged(vec!["roster_digest", "members"])));
+
+    let mut future = manifest.clone();
+    future.version = JOB_FORMAT_VERSION + 1;
+    assert_eq!(future.check_resume(&_job_inputs(&roster)), Err(JobError::UnsupportedVersion(JOB_FORMAT_VERSION + 1)));
+}
@@ -1,7 +1,29 @@
+extern crate alloc;
+
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeMap};
 
+pub mod bench;
+pub mod chunks;
+pub mod compiled;
+pub mod corpus;
+pub mod diagnosis;
+pub mod dx_table;
+pub mod engine;
+pub mod error;
+pub mod hierarchy;
+pub mod interactions;
+pub mod jobs;
+pub mod manifest;
+pub mod merkle;
+pub mod model;
+pub mod population;
+pub mod preview;
+pub mod radv;
+pub mod synthetic;
+pub mod threshold;
 pub mod utils;
+pub mod validation;
 
 /// Public data used in Guest to calculate RAF score for a patient
 #[derive(Debug, Serialize, Deserialize)]
@@ -18,12 +40,18 @@ pub struct PublicRAFInputs {
     // Mapping of ICD-10 codes to HCCs published by CMS
     pub dx_to_cc: BTreeMap<String, Vec<String>>,
 
+    // Diagnostic groups and interaction variables of the model
+    pub interactions: interactions::InteractionDefinitions,
+
+    // Regression variable lists and %SCOREMAC calls parsed from the CMS SAS macros
+    pub model: model::ModelDefinition,
+
     // Normalization factor
     pub norm_factor: f32,
 }
 
 /// Private data used in Guest to calculate RAF score for a patient
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PrivateRAFInput {
     // Array of diagnosis codes for a patient
     pub diagnosis_codes: Vec<String>,
@@ -48,8 +76,17 @@ pub struct PrivateRAFInput {
 
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Journal {
     pub raf_scores: BTreeMap<String, f32>,
     pub coefficients: BTreeMap<String, f32>,
+
+    // SHA-256 of the compiled public tables the scores were computed with, see `compiled::tables_digest`
+    pub tables_digest: merkle::Hash,
+
+    // Root of the dx_to_cc table the diagnosis lookups were verified against
+    pub dx_table: dx_table::DxTableCommitment,
+
+    // Diagnostic group and interaction definitions the scores were computed with
+    pub interactions: interactions::InteractionDefinitions,
 }
\ No newline at end of file
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use crate::error::RAFDataError;
+use crate::merkle::{hash_to_hex, Hash, MerkleHasher};
+
+// Manifest of the CMS files the public tables were compiled from.
+//
+// The compiled tables digest committed by the Guest identifies the tables, but not which CMS
+// release they came from. Receipt bundles carry a `TableManifest` with the SHA-256 of every
+// source file, so the tables can be traced back to the published files.
+
+/// A CMS file and the SHA-256 of its bytes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableFile {
+    // File name within the performance year directory, e.g. V28115H1.TXT
+    pub name: String,
+
+    pub sha256: Hash,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableManifest {
+    pub files: Vec<TableFile>,
+}
+
+impl TableManifest {
+    /// Hashes `files` in `cms_dir`, in the given order
+    pub fn read<H: MerkleHasher>(cms_dir: &str, files: &[&str]) -> Result<Self, RAFDataError> {
+        let mut manifest = TableManifest::default();
+        for name in files {
+            let path = format!("{}/{}", cms_dir, name);
+            let bytes = fs::read(&path).map_err(|source| RAFDataError::Open { path, source })?;
+            manifest.files.push(TableFile { name: name.to_string(), sha256: H::sha256(&bytes) });
+        }
+        return Ok(manifest);
+    }
+
+    /// SHA-256 over one "name<TAB>hex sha256<LF>" line per file
+    pub fn digest<H: MerkleHasher>(&self) -> Hash {
+        let lines: String = self.files.iter().map(|x| format!("{}\t{}\n", x.name, hash_to_hex(&x.sha256))).collect();
+        H::sha256(lines.as_bytes())
+    }
+}
+
+#[test]
+fn can_read_table_manifest() {
+    use crate::merkle::Sha2Hasher;
+    use crate::utils::{get_cms_data_dir, PUBLIC_INPUT_FILES};
+
+    let cms_dir = get_cms_data_dir("PY2023");
+    let manifest = TableManifest::read::<Sha2Hasher>(&cms_dir, &PUBLIC_INPUT_FILES).unwrap();
+    assert_eq!(manifest.files.len(), PUBLIC_INPUT_FILES.len());
+    assert_eq!(manifest.files[2].name, "C2824T2N.csv");
+    assert_eq!(manifest.files[2].sha256, Sha2Hasher::sha256(&fs::read(format!("{}/C2824T2N.csv", cms_dir)).unwrap()));
+    assert_eq!(manifest.digest::<Sha2Hasher>(), TableManifest::read::<Sha2Hasher>(&cms_dir, &PUBLIC_INPUT_FILES).unwrap().digest::<Sha2Hasher>());
+
+    let mut changed = manifest.clone();
+    changed.files[0].sha256[0] ^= 1;
+    assert_ne!(changed.digest::<Sha2Hasher>(), manifest.digest::<Sha2Hasher>());
+
+    let missing = TableManifest::read::<Sha2Hasher>(&cms_dir, &["V28115H1.TXT", "MISSING.TXT"]).unwrap_err();
+    assert!(missing.path().ends_with("MISSING.TXT"));
+}
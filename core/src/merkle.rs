@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// Binary SHA-256 Merkle tree used to commit to public tables too large to hand to the Guest.
+//
+// Leaves and interior nodes are domain separated (0x00 / 0x01 prefix) so a node can never
+// be passed off as a leaf. When a level has an odd number of nodes the last node is
+// carried up unchanged, so proofs only contain real siblings and no padding is needed.
+
+pub type Hash = [u8; 32];
+
+/// SHA-256 implementation used to build and check Merkle proofs. The Host uses
+/// `Sha2Hasher`, the Guest can plug in the zkVM accelerated implementation.
+pub trait MerkleHasher {
+    fn sha256(data: &[u8]) -> Hash;
+
+    fn hash_leaf(data: &[u8]) -> Hash {
+        let mut bytes = Vec::with_capacity(data.len() + 1);
+        bytes.push(0x00);
+        bytes.extend_from_slice(data);
+        Self::sha256(&bytes)
+    }
+
+    fn hash_node(left: &Hash, right: &Hash) -> Hash {
+        let mut bytes = [0u8; 65];
+        bytes[0] = 0x01;
+        bytes[1..33].copy_from_slice(left);
+        bytes[33..].copy_from_slice(right);
+        Self::sha256(&bytes)
+    }
+}
+
+/// Software SHA-256 from the `sha2` crate
+pub struct Sha2Hasher;
+
+impl MerkleHasher for Sha2Hasher {
+    fn sha256(data: &[u8]) -> Hash {
+        Sha256::digest(data).into()
+    }
+}
+
+/// Lower case hex rendering of a hash for logs and reports
+pub fn hash_to_hex(hash: &Hash) -> String {
+    hash.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Parses the rendering of `hash_to_hex`, upper case digits included
+pub fn hash_from_hex(hex: &str) -> Option<Hash> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut hash = [0u8; 32];
+    for (i, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(hash)
+}
+
+/// Every level of the tree, leaves first
+pub struct MerkleTree {
+    levels: Vec<Vec<Hash>>,
+}
+
+impl MerkleTree {
+
+    pub fn from_leaves<H: MerkleHasher>(leaves: Vec<Hash>) -> Self {
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let level = levels.last().unwrap();
+            let next = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => H::hash_node(left, right),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        MerkleTree { levels }
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// Root of the tree, all zeros for a tree without leaves
+    pub fn root(&self) -> Hash {
+        self.levels.last().unwrap().first().copied().unwrap_or([0u8; 32])
+    }
+
+    /// Inclusion proof for the leaf at `index`
+    pub fn proof(&self, index: usize) -> MerkleProof {
+        let mut siblings = vec![];
+        let mut position = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = position ^ 1;
+            if sibling < level.len() {
+                siblings.push(level[sibling]);
+            }
+            position /= 2;
+        }
+        MerkleProof { index: index as u32, siblings }
+    }
+}
+
+/// Sibling hashes from a leaf up to the root
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    pub index: u32,
+    pub siblings: Vec<Hash>,
+}
+
+impl MerkleProof {
+
+    /// Recomputes the root from `leaf`, None if the proof does not fit a tree of `leaf_count` leaves
+    pub fn compute_root<H: MerkleHasher>(&self, leaf: Hash, leaf_count: u32) -> Option<Hash> {
+        if self.index >= leaf_count {
+            return None;
+        }
+        let mut hash = leaf;
+        let mut position = self.index;
+        let mut width = leaf_count;
+        let mut siblings = self.siblings.iter();
+        while width > 1 {
+            let sibling = position ^ 1;
+            if sibling < width {
+                let sibling_hash = siblings.next()?;
+                hash = if position % 2 == 0 {
+                    H::hash_node(&hash, sibling_hash)
+                } else {
+                    H::hash_node(sibling_hash, &hash)
+                };
+            }
+            position /= 2;
+            width = (width + 1) / 2;
+        }
+        if siblings.next().is_some() {
+            return None;
+        }
+        Some(hash)
+    }
+
+    pub fn verify<H: MerkleHasher>(&self, leaf: Hash, leaf_count: u32, root: &Hash) -> bool {
+        self.compute_root::<H>(leaf, leaf_count).as_ref() == Some(root)
+    }
+}
+
+#[test]
+fn can_prove_every_leaf_of_uneven_trees() {
+    for leaf_count in 1..=9u32 {
+        let leaves: Vec<Hash> = (0..leaf_count).map(|i| Sha2Hasher::hash_leaf(&i.to_le_bytes())).collect();
+        let tree = MerkleTree::from_leaves::<Sha2Hasher>(leaves.clone());
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = tree.proof(index);
+            assert!(proof.verify::<Sha2Hasher>(*leaf, leaf_count, &tree.root()));
+            if leaf_count > 1 {
+                assert!(!proof.verify::<Sha2Hasher>(leaves[(index + 1) % leaves.len()], leaf_count, &tree.root()));
+            }
+        }
+    }
+}
+
+#[test]
+fn rejects_out_of_range_proofs() {
+    let leaves: Vec<Hash> = (0..4u32).map(|i| Sha2Hasher::hash_leaf(&i.to_le_bytes())).collect();
+    let tree = MerkleTree::from_leaves::<Sha2Hasher>(leaves.clone());
+    let mut proof = tree.proof(3);
+    proof.index = 4;
+    assert!(!proof.verify::<Sha2Hasher>(leaves[3], 4, &tree.root()));
+    assert_eq!(hash_to_hex(&[0xab; 32]).len(), 64);
+    assert_eq!(hash_from_hex(&hash_to_hex(&tree.root())), Some(tree.root()));
+    assert_eq!(hash_from_hex(&"AB".repeat(32)), Some([0xab; 32]));
+    assert_eq!(hash_from_hex("ab"), None);
+    assert_eq!(hash_from_hex(&"zz".repeat(32)), None);
+}
@@ -0,0 +1,62 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// Regression variable lists and score definitions of the CMS-HCC model.
+// *Parsed from V2823T2P.TXT and V2823T2M.TXT, see `utils::read_model_definition`*
+//
+// The SAS software builds every regression out of %LET lists, e.g.
+//
+//    %LET COMM_REGA= %STR(&AGESEXVA &orig_int &HCClist &INTERRACC_VARSA &ADDZ);
+//
+// and sums the coefficients of each list into a score variable with %SCOREMAC:
+//
+//    %&SCOREMAC(PVAR=SCORE_COMMUNITY_NA,  RLIST=&COMM_REGA, CPREF=CNA_);
+//
+// Lists are kept with their macro references expanded, so the model HCCs (&HCClist)
+// are part of every regression that uses them.
+
+/// A %SCOREMAC call: the score variable, coefficient prefix and the variables it sums
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreDefinition {
+    // Score variable, e.g. SCORE_COMMUNITY_NA
+    pub score: String,
+
+    // Coefficient prefix without its underscore, e.g. CNA
+    pub prefix: String,
+
+    // Name of the %LET list passed as RLIST, e.g. COMM_REGA
+    pub list: String,
+
+    // Regression variables of the list, expanded
+    pub variables: Vec<String>,
+}
+
+/// Regression variable lists and score definitions of a model version
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelDefinition {
+    // Every %LET list made of variable names, expanded and keyed by upper case name
+    pub lists: BTreeMap<String, Vec<String>>,
+
+    // %SCOREMAC calls in the order the main macro makes them
+    pub scores: Vec<ScoreDefinition>,
+}
+
+impl ModelDefinition {
+
+    /// Expanded %LET list, names are case insensitive as in SAS
+    pub fn list(&self, name: &str) -> Option<&[String]> {
+        self.lists.get(&name.to_uppercase()).map(|x| x.as_slice())
+    }
+
+    /// Payment HCCs of the model (&HCClist), empty when the list was not defined
+    pub fn payment_hccs(&self) -> &[String] {
+        self.list("HCClist").unwrap_or(&[])
+    }
+
+    /// Definition of a score variable, e.g. "SCORE_INSTITUTIONAL"
+    pub fn score(&self, score: &str) -> Option<&ScoreDefinition> {
+        self.scores.iter().find(|x| x.score == score)
+    }
+}
@@ -0,0 +1,438 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use crate::compiled::CompiledTablesView;
+use crate::diagnosis::normalize_diagnosis_codes;
+use crate::dx_table::{verified_hccs, DxLookup, DxMerkleTable, DxProofError, DxTableCommitment};
+use crate::engine::{segment_score, ScoringContext};
+use crate::interactions::InteractionDefinitions;
+use crate::merkle::{Hash, MerkleHasher, MerkleProof, MerkleTree};
+use crate::radv::input_commitment;
+use crate::PrivateRAFInput;
+
+// Population level risk scores without per member disclosure.
+//
+// The population Guest scores a whole roster and commits a `PopulationJournal` holding only
+// aggregates: member count, member months, total, mean and member months weighted mean of
+// the score each member is paid on, overall and per eligibility segment. No member score,
+// diagnosis or HCC leaves the Guest, so a plan can prove its population risk for bid
+// validation without handing over individual records.
+//
+// The journal also carries a Merkle root over one `MemberScore` per member, in roster order:
+// the salted commitment of the member's input (`radv::input_commitment`), their segment and
+// score. The Host keeps the leaves and hands out a `MemberScoreProof` for a single member, so
+// that member's score can be disclosed and checked against the receipt on its own.
+//
+// No aggregate covers fewer than `MIN_CELL_SIZE` members. Segments below it are pooled into
+// `SUPPRESSED_SEGMENT`; while the pool itself is too small the smallest published segment
+// joins it, so a suppressed cell cannot be recovered by subtracting the others from the total.
+
+/// Fewest members an aggregate may cover, as in CMS cell suppression (k >= 11)
+pub const MIN_CELL_SIZE: u32 = 11;
+
+/// Segment the members of suppressed segments are aggregated under
+pub const SUPPRESSED_SEGMENT: &str = "SUPPRESSED";
+
+/// A member of the roster with the dx_to_cc evidence for their normalized diagnosis codes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RosterMember {
+    pub input: PrivateRAFInput,
+
+    // Salt of the member commitment, kept by the plan to open it later
+    pub salt: Hash,
+
+    // Months of enrollment in the period, 1 to 12
+    pub member_months: u32,
+
+    pub dx_lookups: Vec<DxLookup>,
+}
+
+impl RosterMember {
+    /// Builds the lookups the Guest checks, one per normalized diagnosis code
+    pub fn new(input: PrivateRAFInput, salt: Hash, member_months: u32, dx_table: &DxMerkleTable) -> Self {
+        let dx_lookups = dx_table.lookups(&normalize_diagnosis_codes(&input.diagnosis_codes));
+        RosterMember { input, salt, member_months, dx_lookups }
+    }
+}
+
+/// Aggregated scores of a group of members
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScoreAggregate {
+    pub members: u32,
+    pub member_months: u32,
+
+    // Sum of the member scores
+    pub total_score: f64,
+
+    // total_score / members
+    pub mean_score: f64,
+
+    // Sum of score * member_months / member_months
+    pub weighted_mean_score: f64,
+}
+
+impl ScoreAggregate {
+    fn add(&mut self, score: f64, member_months: u32) {
+        self.members += 1;
+        self.member_months += member_months;
+        self.total_score += score;
+
+        // Kept as the weighted sum until `finish`
+        self.weighted_mean_score += score * member_months as f64;
+    }
+
+    // Only before `finish`, while the weighted mean is still a sum
+    fn merge(&mut self, other: &ScoreAggregate) {
+        self.members += other.members;
+        self.member_months += other.member_months;
+        self.total_score += other.total_score;
+        self.weighted_mean_score += other.weighted_mean_score;
+    }
+
+    fn finish(&mut self) {
+        if self.members > 0 {
+            self.mean_score = self.total_score / self.members as f64;
+            self.weighted_mean_score /= self.member_months as f64;
+        }
+    }
+}
+
+/// Scored result of a member, a leaf of the results root
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemberScore {
+    // `radv::input_commitment` of the member's input and salt
+    pub member: Hash,
+
+    pub segment: String,
+    pub score: f32,
+}
+
+impl MemberScore {
+
+    /// Bytes hashed into the leaf: the member commitment, the score (f32, little endian), then the segment
+    pub fn leaf_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.member.to_vec();
+        bytes.extend_from_slice(&self.score.to_le_bytes());
+        bytes.extend_from_slice(self.segment.as_bytes());
+        bytes
+    }
+
+    pub fn leaf_hash<H: MerkleHasher>(&self) -> Hash {
+        H::hash_leaf(&self.leaf_bytes())
+    }
+}
+
+/// A member result with its inclusion proof in the results root of a population journal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemberScoreProof {
+    pub result: MemberScore,
+    pub proof: MerkleProof,
+}
+
+impl MemberScoreProof {
+    pub fn verify<H: MerkleHasher>(&self, journal: &PopulationJournal) -> bool {
+        self.proof.verify::<H>(self.result.leaf_hash::<H>(), journal.total.members, &journal.results_root)
+    }
+}
+
+/// Inclusion proofs of every member result, in roster order
+pub fn member_score_proofs<H: MerkleHasher>(results: &[MemberScore]) -> Vec<MemberScoreProof> {
+    let tree = MerkleTree::from_leaves::<H>(results.iter().map(|x| x.leaf_hash::<H>()).collect());
+    results
+        .iter()
+        .enumerate()
+        .map(|(i, result)| MemberScoreProof { result: result.clone(), proof: tree.proof(i) })
+        .collect()
+}
+
+/// What the population Guest commits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PopulationJournal {
+    // Every member, each on the score of their segment
+    pub total: ScoreAggregate,
+
+    // Eligibility segment (e.g. CNA, INS) -> aggregate of its members. Segments below
+    // `min_cell_size` are pooled under `SUPPRESSED_SEGMENT`.
+    pub segments: BTreeMap<String, ScoreAggregate>,
+
+    // `MIN_CELL_SIZE` the segments were suppressed with
+    pub min_cell_size: u32,
+
+    // Merkle root over the `MemberScore` of every member, in roster order
+    pub results_root: Hash,
+
+    // SHA-256 of the compiled public tables, see `compiled::tables_digest`
+    pub tables_digest: Hash,
+
+    // Root of the dx_to_cc table the diagnosis lookups were verified against
+    pub dx_table: DxTableCommitment,
+
+    // Diagnostic group and interaction definitions the scores were computed with
+    pub interactions: InteractionDefinitions,
+}
+
+/// Reasons a roster cannot be scored, by position so no member data is repeated
+#[derive(Debug, Clone, PartialEq)]
+pub enum PopulationError {
+    // The roster has no members, the aggregates would be meaningless
+    EmptyRoster,
+
+    // Fewer members than `MIN_CELL_SIZE`, the total alone would disclose them
+    BelowMinimumCellSize { members: usize },
+
+    // The eligibility code is not one of `engine::SEGMENT_SCORES`
+    UnknownSegment { member: usize, eligibility_code: String },
+
+    // Member months outside 1 to 12
+    InvalidMemberMonths { member: usize, member_months: u32 },
+
+    // A dx_to_cc lookup of the member does not check out
+    InvalidLookup { member: usize, error: DxProofError },
+}
+
+impl fmt::Display for PopulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PopulationError::EmptyRoster => write!(f, "roster has no members"),
+            PopulationError::BelowMinimumCellSize { members } => {
+                write!(f, "roster has {} members, at least {} are needed", members, MIN_CELL_SIZE)
+            }
+            PopulationError::UnknownSegment { member, eligibility_code } => {
+                write!(f, "member {}: unknown eligibility segment {:?}", member, eligibility_code)
+            }
+            PopulationError::InvalidMemberMonths { member, member_months } => {
+                write!(f, "member {}: {} member months, expected 1 to 12", member, member_months)
+            }
+            PopulationError::InvalidLookup { member, error } => write!(f, "member {}: {}", member, error),
+        }
+    }
+}
+
+/*
+    Scores every member of the roster on the score of their eligibility segment and
+    aggregates the results. Runs in the population Guest with the zkVM hasher and natively
+    on the Host with `Sha2Hasher`.
+*/
+pub fn score_population<H: MerkleHasher>(
+    tables: CompiledTablesView,
+    tables_digest: Hash,
+    interactions: &InteractionDefinitions,
+    dx_table: &DxTableCommitment,
+    roster: &[RosterMember],
+) -> Result<PopulationJournal, PopulationError> {
+    score_population_with_results::<H>(tables, tables_digest, interactions, dx_table, roster).map(|(journal, _)| journal)
+}
+
+/// `score_population`, also returning the member results the Host builds inclusion proofs from
+pub fn score_population_with_results<H: MerkleHasher>(
+    tables: CompiledTablesView,
+    tables_digest: Hash,
+    interactions: &InteractionDefinitions,
+    dx_table: &DxTableCommitment,
+    roster: &[RosterMember],
+) -> Result<(PopulationJournal, Vec<MemberScore>), PopulationError> {
+
+    if roster.is_empty() {
+        return Err(PopulationError::EmptyRoster);
+    }
+
+    let context = ScoringContext::new(tables, interactions);
+    let mut total = ScoreAggregate::default();
+    let mut segments = BTreeMap::<String, ScoreAggregate>::new();
+    let mut results = Vec::with_capacity(roster.len());
+    for (member, entry) in roster.iter().enumerate() {
+        let eligibility_code = &entry.input.eligibility_code;
+        let score_name = segment_score(eligibility_code)
+            .ok_or_else(|| PopulationError::UnknownSegment { member, eligibility_code: eligibility_code.clone() })?;
+        if !(1..=12).contains(&entry.member_months) {
+            return Err(PopulationError::InvalidMemberMonths { member, member_months: entry.member_months });
+        }
+
+        let diagnosis_codes = normalize_diagnosis_codes(&entry.input.diagnosis_codes);
+        let hccs = verified_hccs::<H>(&diagnosis_codes, &entry.dx_lookups, dx_table)
+            .map_err(|error| PopulationError::InvalidLookup { member, error })?;
+        let scores = context.score(&entry.input, &hccs);
+        let score = scores.raf_scores.get(score_name).copied().unwrap_or(0.0);
+
+        total.add(score as f64, entry.member_months);
+        segments.entry(eligibility_code.clone()).or_default().add(score as f64, entry.member_months);
+        results.push(MemberScore {
+            member: input_commitment::<H>(&entry.input, &entry.salt),
+            segment: eligibility_code.clone(),
+            score,
+        });
+    }
+
+    // Checked after the members so a malformed roster is reported by position first
+    if total.members < MIN_CELL_SIZE {
+        return Err(PopulationError::BelowMinimumCellSize { members: roster.len() });
+    }
+
+    total.finish();
+    let mut segments = suppress_small_segments(segments);
+    segments.values_mut().for_each(|x| x.finish());
+    let leaves = results.iter().map(|x| x.leaf_hash::<H>()).collect();
+    let journal = PopulationJournal {
+        total,
+        segments,
+        min_cell_size: MIN_CELL_SIZE,
+        results_root: MerkleTree::from_leaves::<H>(leaves).root(),
+        tables_digest,
+        dx_table: *dx_table,
+        interactions: interactions.clone(),
+    };
+    Ok((journal, results))
+}
+
+/*
+    Pools the segments below `MIN_CELL_SIZE` under `SUPPRESSED_SEGMENT`. While the pool is
+    smaller than `MIN_CELL_SIZE`, the smallest published segment is added to it. The roster
+    has at least `MIN_CELL_SIZE` members, so the pool always ends up large enough.
+*/
+fn suppress_small_segments(segments: BTreeMap<String, ScoreAggregate>) -> BTreeMap<String, ScoreAggregate> {
+    let (mut published, small): (BTreeMap<_, _>, BTreeMap<_, _>) = segments.into_iter().partition(|(_, x)| x.members >= MIN_CELL_SIZE);
+    if small.is_empty() {
+        return published;
+    }
+
+    let mut pool = ScoreAggregate::default();
+    small.values().for_each(|x| pool.merge(x));
+    while pool.members < MIN_CELL_SIZE {
+        let smallest = match published.iter().min_by_key(|(_, x)| x.members) {
+            Some((segment, _)) => segment.clone(),
+            None => break,
+        };
+        pool.merge(&published.remove(&smallest).unwrap());
+    }
+    published.insert(SUPPRESSED_SEGMENT.to_string(), pool);
+    return published;
+}
+
+#[cfg(test)]
+fn _member(eligibility_code: &str, age: i32, diagnosis_codes: &[&str]) -> PrivateRAFInput {
+    PrivateRAFInput {
+        diagnosis_codes: diagnosis_codes.iter().map(|x| x.to_string()).collect(),
+        age,
+        sex: "M".to_string(),
+        eligibility_code: eligibility_code.to_string(),
+        entitlement_reason_code: "0".to_string(),
+        medicaid_status: false,
+        long_term_institutionalized: eligibility_code == "INS",
+    }
+}
+
+#[test]
+fn can_aggregate_population_scores() {
+    use crate::compiled::{tables_digest, CompiledRAFTables};
+    use crate::merkle::Sha2Hasher;
+    use crate::preview::score_natively;
+    use crate::utils::{get_cms_data_dir, load_public_inputs};
+
+    let public_inputs = load_public_inputs(&get_cms_data_dir("PY2023")).unwrap();
+    let words = CompiledRAFTables::compile(&public_inputs).unwrap().to_words();
+    let tables = CompiledTablesView::new(&words).unwrap();
+    let dx_table = DxMerkleTable::from_dx_to_cc::<Sha2Hasher>(&public_inputs.dx_to_cc);
+
+    // 12 CNA and 11 CND members are published, the single INS member is pooled with CND
+    let mut members = vec![(_member("INS", 90, &["N184"]), 3)];
+    members.extend((0..12).map(|i| (_member("CNA", 65 + i, if i % 2 == 0 { &["E11.22", "I5020"][..] } else { &[] }), 12 - i as u32 % 3)));
+    members.extend((0..11).map(|i| (_member("CND", 40 + i, &["F200"]), 12)));
+    let roster: Vec<RosterMember> = members
+        .iter()
+        .map(|(input, months)| RosterMember::new(input.clone(), [0; 32], *months, &dx_table))
+        .collect();
+
+    let digest = tables_digest::<Sha2Hasher>(&words);
+    let journal = score_population::<Sha2Hasher>(tables, digest, &public_inputs.interactions, &dx_table.commitment(), &roster).unwrap();
+
+    // Each member is counted on the score of their segment, as scored one by one
+    let segment_scores: Vec<f64> = members
+        .iter()
+        .map(|(input, _)| {
+            let journal = score_natively(tables, &public_inputs.interactions, &dx_table, input).unwrap();
+            journal.raf_scores[segment_score(&input.eligibility_code).unwrap()] as f64
+        })
+        .collect();
+    let total: f64 = segment_scores.iter().sum();
+    let months: u32 = members.iter().map(|(_, months)| months).sum();
+    let weighted = members.iter().zip(&segment_scores).map(|((_, months), score)| score * *months as f64).sum::<f64>() / months as f64;
+
+    assert_eq!((journal.total.members, journal.total.member_months), (24, months));
+    assert!((journal.total.total_score - total).abs() < 1e-9);
+    assert!((journal.total.mean_score - total / 24.0).abs() < 1e-9);
+    assert!((journal.total.weighted_mean_score - weighted).abs() < 1e-9);
+    assert!((journal.segments["CNA"].total_score - segment_scores[1..13].iter().sum::<f64>()).abs() < 1e-9);
+
+    // The INS member is not published on their own, nor recoverable as the total minus CNA and CND
+    assert_eq!(journal.min_cell_size, MIN_CELL_SIZE);
+    assert_eq!(journal.segments.keys().collect::<Vec<_>>(), vec!["CNA", SUPPRESSED_SEGMENT]);
+    assert_eq!((journal.segments["CNA"].members, journal.segments[SUPPRESSED_SEGMENT].members), (12, 12));
+    assert!((journal.segments[SUPPRESSED_SEGMENT].total_score - segment_scores[0] - segment_scores[13..].iter().sum::<f64>()).abs() < 1e-9);
+    assert_eq!(journal.tables_digest, digest);
+}
+
+#[test]
+fn refuses_invalid_rosters() {
+    use crate::compiled::CompiledRAFTables;
+    use crate::merkle::Sha2Hasher;
+    use crate::utils::{get_cms_data_dir, load_public_inputs};
+
+    let public_inputs = load_public_inputs(&get_cms_data_dir("PY2023")).unwrap();
+    let words = CompiledRAFTables::compile(&public_inputs).unwrap().to_words();
+    let tables = CompiledTablesView::new(&words).unwrap();
+    let dx_table = DxMerkleTable::from_dx_to_cc::<Sha2Hasher>(&public_inputs.dx_to_cc);
+    let score = |roster: &[RosterMember]| {
+        score_population::<Sha2Hasher>(tables, [0; 32], &public_inputs.interactions, &dx_table.commitment(), roster).unwrap_err()
+    };
+
+    assert_eq!(score(&[]), PopulationError::EmptyRoster);
+    let unknown = RosterMember::new(_member("XYZ", 70, &[]), [0; 32], 12, &dx_table);
+    assert!(matches!(score(&[unknown]), PopulationError::UnknownSegment { member: 0, .. }));
+    let months = RosterMember::new(_member("CNA", 70, &[]), [0; 32], 13, &dx_table);
+    assert!(matches!(score(&[months]), PopulationError::InvalidMemberMonths { member: 0, member_months: 13 }));
+
+    // Lookups for other codes than the member's cannot stand in for them
+    let mut forged = RosterMember::new(_member("CNA", 70, &["E119"]), [0; 32], 12, &dx_table);
+    forged.input.diagnosis_codes = vec!["C7800".to_string()];
+    let valid = RosterMember::new(_member("CNA", 70, &[]), [0; 32], 12, &dx_table);
+    assert!(matches!(score(&[valid.clone(), forged]), PopulationError::InvalidLookup { member: 1, .. }));
+
+    // Below the minimum cell size the total alone would disclose the members
+    let few = vec![valid; MIN_CELL_SIZE as usize - 1];
+    assert_eq!(score(&few), PopulationError::BelowMinimumCellSize { members: 10 });
+}
+
+#[test]
+fn can_prove_single_member_scores() {
+    use crate::compiled::CompiledRAFTables;
+    use crate::merkle::Sha2Hasher;
+    use crate::utils::{get_cms_data_dir, load_public_inputs};
+
+    let public_inputs = load_public_inputs(&get_cms_data_dir("PY2023")).unwrap();
+    let words = CompiledRAFTables::compile(&public_inputs).unwrap().to_words();
+    let tables = CompiledTablesView::new(&words).unwrap();
+    let dx_table = DxMerkleTable::from_dx_to_cc::<Sha2Hasher>(&public_inputs.dx_to_cc);
+
+    let mut members = vec![("CNA", 70, vec!["E1122"]), ("INS", 90, vec!["N184"]), ("CNA", 80, vec![])];
+    members.resize(MIN_CELL_SIZE as usize, ("CNA", 75, vec![]));
+    let roster: Vec<RosterMember> = members
+        .iter()
+        .enumerate()
+        .map(|(i, (segment, age, codes))| RosterMember::new(_member(segment, *age, codes), [i as u8; 32], 12, &dx_table))
+        .collect();
+    let (journal, results) = score_population_with_results::<Sha2Hasher>(tables, [0; 32], &public_inputs.interactions,
+                                                                          &dx_table.commitment(), &roster).unwrap();
+    assert_eq!(journal, score_population::<Sha2Hasher>(tables, [0; 32], &public_inputs.interactions, &dx_table.commitment(), &roster).unwrap());
+    assert_eq!(results[1].member, input_commitment::<Sha2Hasher>(&roster[1].input, &[1; 32]));
+    assert_eq!(results[1].segment, "INS");
+
+    // Each member is checked on its own, a changed score or segment no longer fits the root
+    let proofs = member_score_proofs::<Sha2Hasher>(&results);
+    assert!(proofs.iter().all(|x| x.verify::<Sha2Hasher>(&journal)));
+    let mut inflated = proofs[0].clone();
+    inflated.result.score += 0.1;
+    assert!(!inflated.verify::<Sha2Hasher>(&journal));
+    let mut moved = proofs[2].clone();
+    moved.result.segment = "INS".to_string();
+    assert!(!moved.verify::<Sha2Hasher>(&journal));
+}
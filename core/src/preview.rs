@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use crate::compiled::{tables_digest, CompiledTablesView};
+use crate::diagnosis::normalize_diagnosis_codes;
+use crate::dx_table::{verified_hccs, DxMerkleTable, DxProofError};
+use crate::engine::ScoringContext;
+use crate::interactions::InteractionDefinitions;
+use crate::merkle::Sha2Hasher;
+use crate::{Journal, PrivateRAFInput};
+
+// Unproven scoring previews.
+//
+// Proving is only needed for final submissions. A preview produces the same `Journal` the
+// Guest commits, either by running the engine natively or by executing the Guest without
+// proving, and wraps it in `UnprovenJournal` so it can never be mistaken for a receipt.
+
+/// How the Host produces scores
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScoringMode {
+    // Scoring engine run directly on the Host
+    Native,
+
+    // Guest executed in the zkVM without `session.prove()`
+    Execute,
+
+    // Guest executed and proven, the only mode producing a receipt
+    Prove,
+}
+
+impl ScoringMode {
+    pub fn parse(mode: &str) -> Option<ScoringMode> {
+        match mode {
+            "native" => Some(ScoringMode::Native),
+            "execute" => Some(ScoringMode::Execute),
+            "prove" => Some(ScoringMode::Prove),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ScoringMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScoringMode::Native => write!(f, "native"),
+            ScoringMode::Execute => write!(f, "execute"),
+            ScoringMode::Prove => write!(f, "prove"),
+        }
+    }
+}
+
+/// A journal computed without a proof; `proven` is always false
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnprovenJournal {
+    pub proven: bool,
+    pub mode: ScoringMode,
+    pub warning: String,
+    pub journal: Journal,
+}
+
+impl UnprovenJournal {
+    pub fn new(mode: ScoringMode, journal: Journal) -> Self {
+        UnprovenJournal {
+            proven: false,
+            mode,
+            warning: format!("UNPROVEN PREVIEW ({} mode): these scores are not backed by a receipt", mode),
+            journal,
+        }
+    }
+}
+
+/*
+    Scores a beneficiary on the Host the way the Guest does: diagnosis codes are normalized,
+    every lookup is checked against the dx_to_cc commitment and the engine output is put in
+    a `Journal`.
+*/
+pub fn score_natively(tables: CompiledTablesView, interactions: &InteractionDefinitions, dx_table: &DxMerkleTable, private_input: &PrivateRAFInput) -> Result<Journal, DxProofError> {
+
+    let commitment = dx_table.commitment();
+    let diagnosis_codes = normalize_diagnosis_codes(&private_input.diagnosis_codes);
+    let flattened_hcc_list = verified_hccs::<Sha2Hasher>(&diagnosis_codes, &dx_table.lookups(&diagnosis_codes), &commitment)?;
+
+    let scores = ScoringContext::new(tables, interactions).score(private_input, &flattened_hcc_list);
+    Ok(Journal {
+        raf_scores: scores.raf_scores,
+        coefficients: BTreeMap::<String, f32>::new(),
+        tables_digest: tables_digest::<Sha2Hasher>(tables.words()),
+        dx_table: commitment,
+        interactions: interactions.clone(),
+    })
+}
+
+#[test]
+fn can_preview_scores_natively() {
+    use crate::compiled::CompiledRAFTables;
+    use crate::utils::{get_cms_data_dir, load_public_inputs};
+
+    let public_inputs = load_public_inputs(&get_cms_data_dir("PY2023")).unwrap();
+    let words = CompiledRAFTables::compile(&public_inputs).unwrap().to_words();
+    let tables = CompiledTablesView::new(&words).unwrap();
+    let dx_table = DxMerkleTable::from_dx_to_cc::<Sha2Hasher>(&public_inputs.dx_to_cc);
+
+    let private_input = PrivateRAFInput {
+        diagnosis_codes: vec!["E11.22".to_string(), "e1122".to_string(), "NOTADX".to_string()],
+        age: 70,
+        sex: "M".to_string(),
+        eligibility_code: "CNA".to_string(),
+        entitlement_reason_code: "0".to_string(),
+        medicaid_status: false,
+        long_term_institutionalized: false,
+    };
+    let journal = score_natively(tables, &public_inputs.interactions, &dx_table, &private_input).unwrap();
+    assert_eq!(journal.dx_table, dx_table.commitment());
+    assert_eq!(journal.tables_digest, tables_digest::<Sha2Hasher>(&words));
+    assert_eq!(journal.interactions, InteractionDefinitions::v28());
+    // CNA_M70_74 + CNA_HCC37 + CNA_D1
+    assert!((journal.raf_scores["SCORE_COMMUNITY_NA"] - (0.396 + 0.166)).abs() < 1e-5);
+
+    let preview = UnprovenJournal::new(ScoringMode::Native, journal);
+    assert!(!preview.proven);
+    assert!(preview.warning.starts_with("UNPROVEN"));
+    assert_eq!(ScoringMode::parse(&ScoringMode::Execute.to_string()), Some(ScoringMode::Execute));
+    assert_eq!(ScoringMode::parse("fast"), None);
+}
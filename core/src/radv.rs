@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use crate::compiled::CompiledTablesView;
+use crate::diagnosis::normalize_diagnosis_codes;
+use crate::dx_table::{verified_hccs, DxLookup, DxProofError, DxRow, DxTableCommitment};
+use crate::engine::ScoringContext;
+use crate::interactions::InteractionDefinitions;
+use crate::merkle::{Hash, MerkleHasher};
+use crate::PrivateRAFInput;
+
+// HCC presence proofs for Risk Adjustment Data Validation (RADV).
+//
+// A plan commits to the private input of each member with `input_commitment`, a salted
+// SHA-256 of the input it keeps alongside its submission. When CMS samples the member, the
+// presence Guest opens that commitment privately, applies the hierarchies and commits an
+// `HccPresenceJournal`: whether the named HCC is present and, if so, the single dx_to_cc row
+// that supports it. The other diagnoses of the member are never committed.
+
+/*
+    Salted commitment to a private input: SHA-256 over the salt, then every field as
+    length-prefixed (u32, little endian) bytes in declaration order, booleans as one byte.
+    The salt keeps the commitment from being brute forced over plausible inputs.
+*/
+pub fn input_commitment<H: MerkleHasher>(private_input: &PrivateRAFInput, salt: &Hash) -> Hash {
+    let mut bytes = salt.to_vec();
+    let mut push = |field: &[u8]| {
+        bytes.extend_from_slice(&(field.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(field);
+    };
+    push(&(private_input.diagnosis_codes.len() as u32).to_le_bytes());
+    for dx in &private_input.diagnosis_codes {
+        push(dx.as_bytes());
+    }
+    push(&private_input.age.to_le_bytes());
+    push(private_input.sex.as_bytes());
+    push(private_input.eligibility_code.as_bytes());
+    push(private_input.entitlement_reason_code.as_bytes());
+    push(&[private_input.medicaid_status as u8]);
+    push(&[private_input.long_term_institutionalized as u8]);
+    H::sha256(&bytes)
+}
+
+/// What the presence Guest commits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HccPresenceJournal {
+    // The HCC CMS asked about, e.g. HCC37
+    pub hcc: String,
+
+    // Whether the HCC is set after the hierarchies
+    pub present: bool,
+
+    // The dx_to_cc row of the first submitted code mapping to the HCC, None when absent
+    pub supporting_row: Option<DxRow>,
+
+    // Commitment the private input was opened against, see `input_commitment`
+    pub input_commitment: Hash,
+
+    // SHA-256 of the compiled public tables, see `compiled::tables_digest`
+    pub tables_digest: Hash,
+
+    // Root of the dx_to_cc table the supporting row was verified against
+    pub dx_table: DxTableCommitment,
+}
+
+/// Reasons HCC presence cannot be proven
+#[derive(Debug, Clone, PartialEq)]
+pub enum HccPresenceError {
+    // The HCC is not one of the model HCCs of the public tables
+    UnknownHcc(String),
+
+    // The private input does not open the commitment
+    CommitmentMismatch,
+
+    InvalidLookup(DxProofError),
+}
+
+impl fmt::Display for HccPresenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HccPresenceError::UnknownHcc(hcc) => write!(f, "{} is not a model HCC", hcc),
+            HccPresenceError::CommitmentMismatch => write!(f, "private input does not match the input commitment"),
+            HccPresenceError::InvalidLookup(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+/*
+    Opens the input commitment, applies the hierarchies to the HCCs of the verified diagnosis
+    codes and reports whether `hcc` is set, with the row of the first code mapping to it.
+    Runs in the presence Guest with the zkVM hasher and natively on the Host with `Sha2Hasher`.
+*/
+#[allow(clippy::too_many_arguments)]
+pub fn prove_hcc_presence<H: MerkleHasher>(
+    tables: CompiledTablesView,
+    tables_digest: Hash,
+    interactions: &InteractionDefinitions,
+    dx_table: &DxTableCommitment,
+    commitment: &Hash,
+    salt: &Hash,
+    private_input: &PrivateRAFInput,
+    dx_lookups: &[DxLookup],
+    hcc: &str,
+) -> Result<HccPresenceJournal, HccPresenceError> {
+
+    if tables.hcc_id(hcc).is_none() {
+        return Err(HccPresenceError::UnknownHcc(hcc.to_string()));
+    }
+    if input_commitment::<H>(private_input, salt) != *commitment {
+        return Err(HccPresenceError::CommitmentMismatch);
+    }
+
+    let diagnosis_codes = normalize_diagnosis_codes(&private_input.diagnosis_codes);
+    let hccs = verified_hccs::<H>(&diagnosis_codes, dx_lookups, dx_table).map_err(HccPresenceError::InvalidLookup)?;
+    let scores = ScoringContext::new(tables, interactions).score(private_input, &hccs);
+    let present = scores.hccs.iter().any(|x| x == hcc);
+
+    // Every lookup was verified above, so the first mapped row naming the HCC supports it
+    let supporting_row = if present {
+        dx_lookups.iter().find_map(|lookup| match lookup {
+            DxLookup::Mapped(row_proof) if row_proof.row.hccs.iter().any(|x| x == hcc) => Some(row_proof.row.clone()),
+            _ => None,
+        })
+    } else {
+        None
+    };
+
+    Ok(HccPresenceJournal {
+        hcc: hcc.to_string(),
+        present,
+        supporting_row,
+        input_commitment: *commitment,
+        tables_digest,
+        dx_table: *dx_table,
+    })
+}
+
+#[test]
+fn can_prove_hcc_presence_after_hierarchies() {
+    use crate::compiled::{tables_digest, CompiledRAFTables};
+    use crate::dx_table::DxMerkleTable;
+    use crate::merkle::Sha2Hasher;
+    use crate::utils::{get_cms_data_dir, load_public_inputs};
+
+    let public_inputs = load_public_inputs(&get_cms_data_dir("PY2023")).unwrap();
+    let words = CompiledRAFTables::compile(&public_inputs).unwrap().to_words();
+    let tables = CompiledTablesView::new(&words).unwrap();
+    let dx_table = DxMerkleTable::from_dx_to_cc::<Sha2Hasher>(&public_inputs.dx_to_cc);
+    let digest = tables_digest::<Sha2Hasher>(&words);
+
+    // E1122 maps to HCC37, which zeroes the HCC38 of E119
+    let private_input = PrivateRAFInput {
+        diagnosis_codes: vec!["E11.9".to_string(), "E1122".to_string(), "I5020".to_string()],
+        age: 70,
+        sex: "M".to_string(),
+        eligibility_code: "CNA".to_string(),
+        entitlement_reason_code: "0".to_string(),
+        medicaid_status: false,
+        long_term_institutionalized: false,
+    };
+    let salt = [7u8; 32];
+    let commitment = input_commitment::<Sha2Hasher>(&private_input, &salt);
+    let lookups = dx_table.lookups(&normalize_diagnosis_codes(&private_input.diagnosis_codes));
+    let prove = |hcc: &str, salt: &Hash| {
+        prove_hcc_presence::<Sha2Hasher>(tables, digest, &public_inputs.interactions, &dx_table.commitment(),
+                                         &commitment, salt, &private_input, &lookups, hcc)
+    };
+
+    let present = prove("HCC37", &salt).unwrap();
+    assert!(present.present);
+    assert_eq!(present.supporting_row.as_ref().map(|x| x.dx.as_str()), Some("E1122"));
+    assert_eq!(present.input_commitment, commitment);
+
+    let zeroed = prove("HCC38", &salt).unwrap();
+    assert!(!zeroed.present && zeroed.supporting_row.is_none());
+
+    assert_eq!(prove("HCC9999", &salt), Err(HccPresenceError::UnknownHcc("HCC9999".to_string())));
+    assert_eq!(prove("HCC37", &[8u8; 32]), Err(HccPresenceError::CommitmentMismatch));
+}
+
+#[test]
+fn input_commitment_binds_every_field() {
+    use crate::merkle::Sha2Hasher;
+
+    let private_input = PrivateRAFInput {
+        diagnosis_codes: vec!["E119".to_string()],
+        age: 70,
+        sex: "M".to_string(),
+        eligibility_code: "CNA".to_string(),
+        entitlement_reason_code: "0".to_string(),
+        medicaid_status: false,
+        long_term_institutionalized: false,
+    };
+    let salt = [1u8; 32];
+    let commitment = input_commitment::<Sha2Hasher>(&private_input, &salt);
+
+    let mut changed = private_input.clone();
+    changed.diagnosis_codes = vec!["E11".to_string(), "9".to_string()];
+    assert_ne!(input_commitment::<Sha2Hasher>(&changed, &salt), commitment);
+    let mut changed = private_input.clone();
+    changed.medicaid_status = true;
+    assert_ne!(input_commitment::<Sha2Hasher>(&changed, &salt), commitment);
+    assert_ne!(input_commitment::<Sha2Hasher>(&private_input, &[2u8; 32]), commitment);
+}
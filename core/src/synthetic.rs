@@ -0,0 +1,342 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use crate::PrivateRAFInput;
+
+// Synthetic beneficiaries for tests and benchmarks.
+//
+// Demographics are drawn from a `PopulationProfile` shaped like the Medicare population
+// (mostly aged, some disabled, duals and institutional members) and diagnosis codes are drawn
+// from the real dx_to_cc table, so scoring, batching and proving can be stressed without PHI.
+// A population is fully determined by its profile, the table and the seed.
+
+// xorshift64*, enough to draw reproducible populations without pulling in an RNG crate
+pub struct SyntheticRng(u64);
+
+impl SyntheticRng {
+    pub fn new(seed: u64) -> Self {
+        SyntheticRng(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Uniform in 0..n, `n` must not be 0
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// True `percent` times out of 100
+    pub fn percent(&mut self, percent: u32) -> bool {
+        self.below(100) < percent as usize
+    }
+
+    /// One of the values, drawn in proportion to its weight. `table` must not be empty.
+    pub fn weighted<'a, T>(&mut self, table: &'a [(T, u32)]) -> &'a T {
+        let total: u32 = table.iter().map(|x| x.1).sum();
+        let mut draw = self.below(total.max(1) as usize) as u32;
+        for (value, weight) in table {
+            if draw < *weight {
+                return value;
+            }
+            draw -= weight;
+        }
+        return &table[table.len() - 1].0;
+    }
+}
+
+/// Distributions a synthetic population is drawn from, weights are relative
+#[derive(Debug, Clone)]
+pub struct PopulationProfile {
+    // Inclusive age ranges and their weights
+    pub age_bands: Vec<((i32, i32), u32)>,
+
+    // Share of women, in percent
+    pub female_percent: u32,
+
+    // Original reason for entitlement (OREC) under 65 and from 65 on
+    pub orec_under_65: Vec<(&'static str, u32)>,
+    pub orec_aged: Vec<(&'static str, u32)>,
+
+    // Share of Medicaid duals and, among them, of full benefit duals, in percent
+    pub dual_percent: u32,
+    pub full_dual_percent: u32,
+
+    // Share of long term institutionalized members, in percent
+    pub institutional_percent: u32,
+
+    // Number of diagnosis codes per member and its weight
+    pub dx_counts: Vec<(usize, u32)>,
+
+    // Share of diagnosis codes that are not in dx_to_cc, in percent
+    pub unmapped_dx_percent: u32,
+
+    // Weight of each CC when a mapped diagnosis code is drawn, by the first CC the code maps
+    // to (e.g. HCC38), and the weight of every CC that is not listed
+    pub cc_prevalence: Vec<(&'static str, u32)>,
+    pub other_cc_weight: u32,
+}
+
+/// Reasons a profile cannot be drawn from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileError {
+    // A weighted table is empty or all of its weights are zero
+    EmptyTable(&'static str),
+
+    // An age band whose low end is above its high end
+    InvalidAgeBand(i32, i32),
+
+    // A share over 100 percent
+    InvalidPercent(&'static str, u32),
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::EmptyTable(table) => write!(f, "{} has no value with a weight", table),
+            ProfileError::InvalidAgeBand(low, high) => write!(f, "age band {}-{} is empty", low, high),
+            ProfileError::InvalidPercent(share, percent) => write!(f, "{} is {}%, over 100%", share, percent),
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+impl PopulationProfile {
+
+    /// Checks every table can be drawn from and every share is a percentage
+    pub fn validate(&self) -> Result<(), ProfileError> {
+        fn drawable<T>(name: &'static str, table: &[(T, u32)]) -> Result<(), ProfileError> {
+            if table.iter().all(|x| x.1 == 0) {
+                return Err(ProfileError::EmptyTable(name));
+            }
+            Ok(())
+        }
+        drawable("age_bands", &self.age_bands)?;
+        drawable("orec_under_65", &self.orec_under_65)?;
+        drawable("orec_aged", &self.orec_aged)?;
+        drawable("dx_counts", &self.dx_counts)?;
+        if let Some(((low, high), _)) = self.age_bands.iter().find(|((low, high), _)| low > high) {
+            return Err(ProfileError::InvalidAgeBand(*low, *high));
+        }
+        for (share, percent) in [
+            ("female_percent", self.female_percent),
+            ("dual_percent", self.dual_percent),
+            ("full_dual_percent", self.full_dual_percent),
+            ("institutional_percent", self.institutional_percent),
+            ("unmapped_dx_percent", self.unmapped_dx_percent),
+        ] {
+            if percent > 100 {
+                return Err(ProfileError::InvalidPercent(share, percent));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for PopulationProfile {
+    fn default() -> Self {
+        PopulationProfile {
+            age_bands: vec![
+                ((21, 44), 4), ((45, 64), 9), ((65, 69), 22), ((70, 74), 22),
+                ((75, 79), 17), ((80, 84), 12), ((85, 89), 8), ((90, 99), 6),
+            ],
+            female_percent: 55,
+            orec_under_65: vec![("1", 90), ("2", 5), ("3", 5)],
+            orec_aged: vec![("0", 80), ("1", 18), ("2", 1), ("3", 1)],
+            dual_percent: 18,
+            full_dual_percent: 65,
+            institutional_percent: 3,
+            dx_counts: vec![
+                (0, 15), (1, 15), (2, 14), (3, 12), (4, 10), (5, 8),
+                (6, 7), (8, 7), (10, 6), (15, 4), (20, 2),
+            ],
+            unmapped_dx_percent: 10,
+
+            // Rough shares per 10,000 Medicare beneficiaries, so chronic conditions such as
+            // diabetes, arrhythmias and COPD are common and HIV/AIDS or transplants are rare.
+            // Illustrative orders of magnitude, not published CMS figures.
+            cc_prevalence: vec![
+                ("HCC38", 1500), ("HCC37", 900), ("HCC36", 30), ("HCC238", 1100), ("HCC280", 1000),
+                ("HCC226", 800), ("HCC227", 150), ("HCC228", 60), ("HCC229", 40), ("HCC224", 60),
+                ("HCC225", 30), ("HCC222", 20), ("HCC329", 700), ("HCC328", 300), ("HCC327", 120),
+                ("HCC326", 30), ("HCC155", 500), ("HCC154", 80), ("HCC152", 60), ("HCC151", 50),
+                ("HCC127", 400), ("HCC126", 80), ("HCC125", 40), ("HCC48", 350), ("HCC23", 500),
+                ("HCC22", 150), ("HCC21", 80), ("HCC20", 60), ("HCC19", 70), ("HCC18", 40),
+                ("HCC17", 30), ("HCC93", 250), ("HCC267", 200), ("HCC264", 150), ("HCC263", 40),
+                ("HCC249", 120), ("HCC253", 80), ("HCC201", 200), ("HCC199", 120), ("HCC198", 40),
+                ("HCC137", 60), ("HCC139", 80), ("HCC298", 150), ("HCC300", 200), ("HCC2", 80),
+                ("HCC64", 50), ("HCC65", 40), ("HCC401", 60), ("HCC402", 50), ("HCC383", 40),
+                ("HCC382", 40), ("HCC381", 20), ("HCC379", 10), ("HCC409", 20), ("HCC1", 20),
+                ("HCC454", 5), ("HCC190", 5), ("HCC180", 5), ("HCC181", 5), ("HCC107", 3),
+                ("HCC62", 3), ("HCC35", 2), ("HCC221", 2), ("HCC276", 2), ("HCC277", 2),
+                ("HCC77", 1), ("HCC111", 1),
+            ],
+            other_cc_weight: 10,
+        }
+    }
+}
+
+/// Seeded generator of synthetic beneficiaries
+pub struct SyntheticPopulation<'a> {
+    rng: SyntheticRng,
+    profile: PopulationProfile,
+
+    // dx_to_cc codes grouped by the first CC they map to, with the weight of the CC. CCs are
+    // drawn by their prevalence in the profile, codes of a CC uniformly.
+    codes_by_cc: Vec<(Vec<&'a String>, u32)>,
+}
+
+impl<'a> SyntheticPopulation<'a> {
+
+    pub fn new(dx_to_cc: &'a BTreeMap<String, Vec<String>>, profile: PopulationProfile, seed: u64) -> Result<Self, ProfileError> {
+        profile.validate()?;
+        let mut grouped = BTreeMap::<&String, Vec<&String>>::new();
+        for (dx, ccs) in dx_to_cc {
+            if let Some(cc) = ccs.first() {
+                grouped.entry(cc).or_default().push(dx);
+            }
+        }
+        let weight = |cc: &String| match profile.cc_prevalence.iter().find(|x| x.0 == cc) {
+            Some((_, weight)) => *weight,
+            None => profile.other_cc_weight,
+        };
+        let codes_by_cc = grouped
+            .into_iter()
+            .map(|(cc, codes)| (codes, weight(cc)))
+            .filter(|x| x.1 > 0)
+            .collect();
+        Ok(SyntheticPopulation {
+            rng: SyntheticRng::new(seed),
+            profile,
+            codes_by_cc,
+        })
+    }
+
+    // A diagnosis code from dx_to_cc, or a well formed code outside of it
+    fn next_dx(&mut self) -> String {
+        if self.codes_by_cc.is_empty() || self.rng.percent(self.profile.unmapped_dx_percent) {
+            return format!("Z{:04}X", self.rng.below(10_000));
+        }
+        let codes = self.rng.weighted(&self.codes_by_cc);
+        return codes[self.rng.below(codes.len())].clone();
+    }
+
+    /// Draws the next beneficiary of the population
+    pub fn next_beneficiary(&mut self) -> PrivateRAFInput {
+
+        let (low, high) = *self.rng.weighted(&self.profile.age_bands);
+        let age = low + self.rng.below((high - low + 1) as usize) as i32;
+        let sex = if self.rng.percent(self.profile.female_percent) { "F" } else { "M" };
+        let orec = if age < 65 {
+            *self.rng.weighted(&self.profile.orec_under_65)
+        } else {
+            *self.rng.weighted(&self.profile.orec_aged)
+        };
+        let medicaid = self.rng.percent(self.profile.dual_percent);
+        let full_dual = medicaid && self.rng.percent(self.profile.full_dual_percent);
+        let institutional = self.rng.percent(self.profile.institutional_percent);
+
+        // Segment of the score the member is paid on, e.g. CFA: community, full dual, aged
+        let eligibility_code = if institutional {
+            "INS".to_string()
+        } else {
+            let dual = if full_dual { "F" } else if medicaid { "P" } else { "N" };
+            let aged = if age < 65 && orec != "0" { "D" } else { "A" };
+            format!("C{}{}", dual, aged)
+        };
+
+        let dx_count = *self.rng.weighted(&self.profile.dx_counts);
+        let diagnosis_codes = (0..dx_count).map(|_| self.next_dx()).collect();
+
+        PrivateRAFInput {
+            diagnosis_codes,
+            age,
+            sex: sex.to_string(),
+            eligibility_code,
+            entitlement_reason_code: orec.to_string(),
+            medicaid_status: medicaid,
+            long_term_institutionalized: institutional,
+        }
+    }
+
+    /// The next `members` beneficiaries
+    pub fn roster(&mut self, members: usize) -> Vec<PrivateRAFInput> {
+        (0..members).map(|_| self.next_beneficiary()).collect()
+    }
+}
+
+#[cfg(test)]
+fn _published_dx_to_cc() -> BTreeMap<String, Vec<String>> {
+    use crate::utils::{get_cms_data_dir, read_dx_to_cc};
+    read_dx_to_cc(&format!("{}/F2823T2N_FY22FY23.TXT", get_cms_data_dir("PY2023"))).unwrap()
+}
+
+#[test]
+fn can_generate_reproducible_populations() {
+    let dx_to_cc = _published_dx_to_cc();
+    let roster = SyntheticPopulation::new(&dx_to_cc, PopulationProfile::default(), 7).unwrap().roster(50);
+    let again = SyntheticPopulation::new(&dx_to_cc, PopulationProfile::default(), 7).unwrap().roster(50);
+    let other = SyntheticPopulation::new(&dx_to_cc, PopulationProfile::default(), 8).unwrap().roster(50);
+
+    let codes = |roster: &[PrivateRAFInput]| roster.iter().map(|x| (x.age, x.diagnosis_codes.clone())).collect::<Vec<_>>();
+    assert_eq!(codes(&roster), codes(&again));
+    assert_ne!(codes(&roster), codes(&other));
+}
+
+#[test]
+fn can_generate_population_from_profile() {
+    let dx_to_cc = _published_dx_to_cc();
+    let roster = SyntheticPopulation::new(&dx_to_cc, PopulationProfile::default(), 42).unwrap().roster(2000);
+
+    let share = |f: &dyn Fn(&PrivateRAFInput) -> bool| roster.iter().filter(|x| f(x)).count() * 100 / roster.len();
+    assert!((80..=93).contains(&share(&|x| x.age >= 65)));
+    assert!((50..=60).contains(&share(&|x| x.sex == "F")));
+    assert!((14..=22).contains(&share(&|x| x.medicaid_status)));
+    assert!((1..=5).contains(&share(&|x| x.long_term_institutionalized)));
+    assert!(roster.iter().all(|x| x.age >= 21 && x.age <= 99));
+    assert!(roster.iter().all(|x| x.age >= 65 || x.entitlement_reason_code != "0"));
+    assert!(roster.iter().filter(|x| x.long_term_institutionalized).all(|x| x.eligibility_code == "INS"));
+
+    // Mapped codes come from the real table, the rest never collide with it
+    let all_codes: Vec<&String> = roster.iter().flat_map(|x| x.diagnosis_codes.iter()).collect();
+    let mapped = all_codes.iter().filter(|x| dx_to_cc.contains_key(**x)).count();
+    assert!(mapped * 100 / all_codes.len() >= 85);
+    assert!(all_codes.iter().filter(|x| !dx_to_cc.contains_key(**x)).all(|x| x.starts_with('Z') && x.ends_with('X')));
+
+    // A fixed number of codes per member, as the benchmark uses
+    let profile = PopulationProfile { dx_counts: vec![(4, 1)], ..PopulationProfile::default() };
+    let roster = SyntheticPopulation::new(&dx_to_cc, profile, 42).unwrap().roster(20);
+    assert!(roster.iter().all(|x| x.diagnosis_codes.len() == 4));
+}
+
+#[test]
+fn draws_common_conditions_more_often_than_rare_ones() {
+    let dx_to_cc = _published_dx_to_cc();
+    let profile = PopulationProfile { unmapped_dx_percent: 0, ..PopulationProfile::default() };
+    let roster = SyntheticPopulation::new(&dx_to_cc, profile, 42).unwrap().roster(2000);
+
+    let members_with = |cc: &str| roster.iter().filter(|x| x.diagnosis_codes.iter().any(|dx| dx_to_cc[dx][0] == cc)).count();
+    assert!(members_with("HCC38") > 10 * members_with("HCC1").max(1));
+    assert!(members_with("HCC238") > 10 * members_with("HCC276").max(1));
+}
+
+#[test]
+fn rejects_profiles_that_cannot_be_drawn_from() {
+    let dx_to_cc = _published_dx_to_cc();
+    let draw = |profile: PopulationProfile| SyntheticPopulation::new(&dx_to_cc, profile, 42).err();
+
+    assert_eq!(draw(PopulationProfile { age_bands: vec![], ..PopulationProfile::default() }), Some(ProfileError::EmptyTable("age_bands")));
+    assert_eq!(draw(PopulationProfile { dx_counts: vec![(3, 0)], ..PopulationProfile::default() }), Some(ProfileError::EmptyTable("dx_counts")));
+    assert_eq!(draw(PopulationProfile { orec_aged: vec![], ..PopulationProfile::default() }), Some(ProfileError::EmptyTable("orec_aged")));
+    assert_eq!(draw(PopulationProfile { age_bands: vec![((70, 65), 1)], ..PopulationProfile::default() }), Some(ProfileError::InvalidAgeBand(70, 65)));
+    assert_eq!(draw(PopulationProfile { dual_percent: 101, ..PopulationProfile::default() }), Some(ProfileError::InvalidPercent("dual_percent", 101)));
+
+    // Without any CC weight every code is drawn outside of dx_to_cc
+    let profile = PopulationProfile { cc_prevalence: vec![], other_cc_weight: 0, ..PopulationProfile::default() };
+    let roster = SyntheticPopulation::new(&dx_to_cc, profile, 42).unwrap().roster(20);
+    assert!(roster.iter().flat_map(|x| x.diagnosis_codes.iter()).all(|x| !dx_to_cc.contains_key(x)));
+}
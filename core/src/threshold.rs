@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use crate::compiled::CompiledTablesView;
+use crate::diagnosis::normalize_diagnosis_codes;
+use crate::dx_table::{verified_hccs, DxLookup, DxProofError, DxTableCommitment};
+use crate::engine::{segment_score, ScoringContext};
+use crate::interactions::InteractionDefinitions;
+use crate::merkle::{Hash, MerkleHasher};
+use crate::radv::input_commitment;
+use crate::PrivateRAFInput;
+
+// Threshold and range proofs on the risk score of a beneficiary.
+//
+// A care management vendor only needs to know whether a member qualifies for a program, not
+// the member's score. The threshold Guest scores the member on the score of their eligibility
+// segment and commits a `ThresholdJournal`: the public condition, whether the score meets it,
+// the segment used and the digest of the public tables. Neither the score nor any diagnosis
+// is committed. As for RADV, the member is identified by the salted commitment of their input
+// (`radv::input_commitment`), which the Guest opens privately, so the answer is tied to the
+// member the plan committed to.
+
+/// Public condition on a score, given by the party asking for the proof
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ScoreCondition {
+    // score >= cutoff
+    AtLeast(f32),
+
+    // low <= score < high
+    Between { low: f32, high: f32 },
+}
+
+impl ScoreCondition {
+    /// A condition every finite score can be checked against
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            ScoreCondition::AtLeast(cutoff) => cutoff.is_finite(),
+            ScoreCondition::Between { low, high } => low.is_finite() && high.is_finite() && low < high,
+        }
+    }
+
+    pub fn is_met(&self, score: f32) -> bool {
+        match *self {
+            ScoreCondition::AtLeast(cutoff) => score >= cutoff,
+            ScoreCondition::Between { low, high } => low <= score && score < high,
+        }
+    }
+}
+
+impl fmt::Display for ScoreCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScoreCondition::AtLeast(cutoff) => write!(f, "score >= {}", cutoff),
+            ScoreCondition::Between { low, high } => write!(f, "{} <= score < {}", low, high),
+        }
+    }
+}
+
+/// What the threshold Guest commits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdJournal {
+    pub condition: ScoreCondition,
+
+    // Whether the score of the segment meets the condition
+    pub met: bool,
+
+    // Eligibility segment and the score variable that was checked, e.g. CNA, SCORE_COMMUNITY_NA
+    pub segment: String,
+    pub score: String,
+
+    // Commitment the private input was opened against, see `radv::input_commitment`
+    pub input_commitment: Hash,
+
+    // SHA-256 of the compiled public tables, see `compiled::tables_digest`
+    pub tables_digest: Hash,
+
+    // Root of the dx_to_cc table the diagnosis lookups were verified against
+    pub dx_table: DxTableCommitment,
+
+    // Diagnostic group and interaction definitions the score was computed with
+    pub interactions: InteractionDefinitions,
+}
+
+/// Reasons a threshold cannot be evaluated
+#[derive(Debug, Clone, PartialEq)]
+pub enum ThresholdError {
+    // NaN or infinite bounds, or an empty range
+    InvalidCondition(ScoreCondition),
+
+    // The eligibility code is not one of `engine::SEGMENT_SCORES`
+    UnknownSegment(String),
+
+    // The private input does not open the commitment
+    CommitmentMismatch,
+
+    InvalidLookup(DxProofError),
+}
+
+impl fmt::Display for ThresholdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThresholdError::InvalidCondition(condition) => write!(f, "condition {} cannot be checked", condition),
+            ThresholdError::UnknownSegment(code) => write!(f, "unknown eligibility segment {:?}", code),
+            ThresholdError::CommitmentMismatch => write!(f, "private input does not match the input commitment"),
+            ThresholdError::InvalidLookup(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+/*
+    Opens the input commitment, scores the beneficiary on the score of their eligibility
+    segment and checks it against the condition. Runs in the threshold Guest with the zkVM hasher and natively on the Host
+    with `Sha2Hasher`.
+*/
+#[allow(clippy::too_many_arguments)]
+pub fn evaluate_threshold<H: MerkleHasher>(
+    tables: CompiledTablesView,
+    tables_digest: Hash,
+    interactions: &InteractionDefinitions,
+    dx_table: &DxTableCommitment,
+    commitment: &Hash,
+    salt: &Hash,
+    private_input: &PrivateRAFInput,
+    dx_lookups: &[DxLookup],
+    condition: ScoreCondition,
+) -> Result<ThresholdJournal, ThresholdError> {
+
+    if !condition.is_valid() {
+        return Err(ThresholdError::InvalidCondition(condition));
+    }
+    let segment = &private_input.eligibility_code;
+    let score_name = segment_score(segment).ok_or_else(|| ThresholdError::UnknownSegment(segment.clone()))?;
+    if input_commitment::<H>(private_input, salt) != *commitment {
+        return Err(ThresholdError::CommitmentMismatch);
+    }
+
+    let diagnosis_codes = normalize_diagnosis_codes(&private_input.diagnosis_codes);
+    let hccs = verified_hccs::<H>(&diagnosis_codes, dx_lookups, dx_table).map_err(ThresholdError::InvalidLookup)?;
+    let scores = ScoringContext::new(tables, interactions).score(private_input, &hccs);
+    let score = scores.raf_scores.get(score_name).copied().unwrap_or(0.0);
+
+    Ok(ThresholdJournal {
+        condition,
+        met: condition.is_met(score),
+        segment: segment.clone(),
+        score: score_name.to_string(),
+        input_commitment: *commitment,
+        tables_digest,
+        dx_table: *dx_table,
+        interactions: interactions.clone(),
+    })
+}
+
+#[test]
+fn can_check_score_conditions() {
+    assert!(ScoreCondition::AtLeast(1.5).is_met(1.5));
+    assert!(!ScoreCondition::AtLeast(1.5).is_met(1.4999));
+    let range = ScoreCondition::Between { low: 1.0, high: 2.0 };
+    assert!(range.is_met(1.0) && range.is_met(1.99) && !range.is_met(2.0) && !range.is_met(0.5));
+    assert!(!ScoreCondition::AtLeast(f32::NAN).is_valid());
+    assert!(!ScoreCondition::Between { low: 2.0, high: 1.0 }.is_valid());
+    assert_eq!(range.to_string(), "1 <= score < 2");
+}
+
+#[test]
+fn can_evaluate_threshold_without_revealing_score() {
+    use crate::compiled::{tables_digest, CompiledRAFTables};
+    use crate::dx_table::DxMerkleTable;
+    use crate::merkle::Sha2Hasher;
+    use crate::utils::{get_cms_data_dir, load_public_inputs};
+
+    let public_inputs = load_public_inputs(&get_cms_data_dir("PY2023")).unwrap();
+    let words = CompiledRAFTables::compile(&public_inputs).unwrap().to_words();
+    let tables = CompiledTablesView::new(&words).unwrap();
+    let dx_table = DxMerkleTable::from_dx_to_cc::<Sha2Hasher>(&public_inputs.dx_to_cc);
+    let digest = tables_digest::<Sha2Hasher>(&words);
+
+    // CNA_M70_74 + CNA_HCC37 + CNA_HCC226 + CNA_DIABETES_HF_V28 + CNA_D2 = 1.034
+    let private_input = PrivateRAFInput {
+        diagnosis_codes: vec!["E11.22".to_string(), "I5020".to_string()],
+        age: 70,
+        sex: "M".to_string(),
+        eligibility_code: "CNA".to_string(),
+        entitlement_reason_code: "0".to_string(),
+        medicaid_status: false,
+        long_term_institutionalized: false,
+    };
+    let lookups = dx_table.lookups(&normalize_diagnosis_codes(&private_input.diagnosis_codes));
+    let salt = [7; 32];
+    let commitment = input_commitment::<Sha2Hasher>(&private_input, &salt);
+    let evaluate = |condition| {
+        evaluate_threshold::<Sha2Hasher>(tables, digest, &public_inputs.interactions, &dx_table.commitment(),
+                                         &commitment, &salt, &private_input, &lookups, condition)
+    };
+
+    let journal = evaluate(ScoreCondition::AtLeast(1.0)).unwrap();
+    assert!(journal.met);
+    assert_eq!((journal.segment.as_str(), journal.score.as_str()), ("CNA", "SCORE_COMMUNITY_NA"));
+    assert_eq!((journal.input_commitment, journal.tables_digest), (commitment, digest));
+    assert!(!evaluate(ScoreCondition::AtLeast(1.1)).unwrap().met);
+    assert!(evaluate(ScoreCondition::Between { low: 1.0, high: 1.1 }).unwrap().met);
+    assert!(matches!(evaluate(ScoreCondition::Between { low: 1.1, high: 1.0 }), Err(ThresholdError::InvalidCondition(_))));
+
+    // Lookups of other codes are rejected rather than scored as unmapped
+    let wrong_lookups = dx_table.lookups(&["E119".to_string(), "I5020".to_string()]);
+    let forged = evaluate_threshold::<Sha2Hasher>(tables, digest, &public_inputs.interactions, &dx_table.commitment(),
+                                                  &commitment, &salt, &private_input, &wrong_lookups, ScoreCondition::AtLeast(1.0));
+    assert!(matches!(forged, Err(ThresholdError::InvalidLookup(_))));
+
+    // Another member cannot be answered for under this member's commitment
+    let mut other = private_input.clone();
+    other.age = 85;
+    let swapped = evaluate_threshold::<Sha2Hasher>(tables, digest, &public_inputs.interactions, &dx_table.commitment(),
+                                                   &commitment, &salt, &other, &lookups, ScoreCondition::AtLeast(1.0));
+    assert_eq!(swapped, Err(ThresholdError::CommitmentMismatch));
+}
@@ -1,9 +1,13 @@
 use regex::Regex;
 use std::collections::BTreeMap;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{BufRead, BufReader};
 use std::env;
 use csv::ReaderBuilder;
+use crate::error::RAFDataError;
+use crate::interactions::InteractionDefinitions;
+use crate::PublicRAFInputs;
+use crate::model::{ModelDefinition, ScoreDefinition};
 
 /*
     Finds the path to the CMS Data directory for the given performance year.
@@ -20,19 +24,27 @@ pub fn get_cms_data_dir(performance_year: &str) -> String {
 }
 
 
+/*
+    Opens a CMS data file, attaching the path to any failure
+*/
+fn open_data_file(filename: &str) -> Result<BufReader<File>, RAFDataError> {
+    match File::open(filename) {
+        Ok(file) => Ok(BufReader::new(file)),
+        Err(source) => Err(RAFDataError::Open { path: filename.to_string(), source }),
+    }
+}
+
 /*
     Reads in label file and returns a dictionary of HCC to label
 */
-pub fn read_hcc_labels(filename: &str) -> Result<BTreeMap<String, String>, csv::Error> {
+pub fn read_hcc_labels(filename: &str) -> Result<BTreeMap<String, String>, RAFDataError> {
 
     let mut labels = BTreeMap::new();
-    let file = File::open(filename)?;
-    let reader = BufReader::new(file);
+    let reader = open_data_file(filename)?;
     let re = Regex::new(r#"\s*((?:HCC|CC)\d+)\s*=\s*"([^"]+)"#).unwrap();
 
-    
-    for line in reader.lines() {
-        let line = line.unwrap();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|source| RAFDataError::Read { path: filename.to_string(), line: index + 1, source })?;
         if let Some(captures) = re.captures(&line) {
             let hcc = captures.get(1).unwrap().as_str();
             let label = captures.get(2).unwrap().as_str();
@@ -40,59 +52,99 @@ pub fn read_hcc_labels(filename: &str) -> Result<BTreeMap<String, String>, csv::
         }
     }
 
+    if labels.is_empty() {
+        return Err(RAFDataError::Empty { path: filename.to_string() });
+    }
     Ok(labels)
 }
 
-pub fn read_hier(filename: &str) -> Result<BTreeMap<String, Vec<String>>, csv::Error> {
+/*
+    Reads in the SAS hierarchy macro and returns a dictionary of each superior HCC
+    to the list of HCCs it zeroes out
+*/
+pub fn read_hier(filename: &str) -> Result<BTreeMap<String, Vec<String>>, RAFDataError> {
 
     let mut hiers = BTreeMap::new();
     let pttr = Regex::new(r"%SET0\(CC=(\d+).+%STR\((.+)\)\)").unwrap();
-    let file = File::open(filename)?;
-    let reader = BufReader::new(file);
-    for line in reader.lines() {
-        let line = line.unwrap();
+    let reader = open_data_file(filename)?;
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|source| RAFDataError::Read { path: filename.to_string(), line: index + 1, source })?;
         let matches = pttr.captures(&line);
         if let Some(caps) = matches {
             let k = "HCC".to_owned() + &caps[1];
-            let v: Vec<String> = caps[2]
-                .split(',')
-                .map(|x| "HCC".to_owned() + x.trim())
-                .collect();
+            let mut v: Vec<String> = vec![];
+            for x in caps[2].split(',') {
+                let x = x.trim();
+                if x.is_empty() || !x.chars().all(|c| c.is_ascii_digit()) {
+                    return Err(RAFDataError::Parse {
+                        path: filename.to_string(),
+                        line: index + 1,
+                        message: format!("invalid inferior CC '{}' in hierarchy for {}", x, k),
+                    });
+                }
+                v.push("HCC".to_owned() + x);
+            }
             hiers.insert(k, v);
         }
     }
+
+    if hiers.is_empty() {
+        return Err(RAFDataError::Empty { path: filename.to_string() });
+    }
     Ok(hiers)
 }
 
 /*
     Reads in a CSV file and returns a dictionary of HCC conditions to decimal coefficients
 */
-pub fn read_hcc_coefficients(filename: &str) -> Result<BTreeMap<String, f32>, csv::Error> {
+pub fn read_hcc_coefficients(filename: &str) -> Result<BTreeMap<String, f32>, RAFDataError> {
 
-    let file = File::open(filename)?;
-    let mut reader = BufReader::new(file);
+    let mut reader = open_data_file(filename)?;
     let mut map = BTreeMap::new();
     let mut headers = String::new();
-    reader.read_line(&mut headers);
+    reader.read_line(&mut headers)
+        .map_err(|source| RAFDataError::Read { path: filename.to_string(), line: 1, source })?;
     let mut values = String::new();
-    reader.read_line(&mut values);
+    reader.read_line(&mut values)
+        .map_err(|source| RAFDataError::Read { path: filename.to_string(), line: 2, source })?;
+
+    if headers.trim().is_empty() {
+        return Err(RAFDataError::Empty { path: filename.to_string() });
+    }
+    if values.trim().is_empty() {
+        return Err(RAFDataError::Parse {
+            path: filename.to_string(),
+            line: 2,
+            message: "missing coefficient values below the header row".to_string(),
+        });
+    }
 
     // Split headers into Vector of strings split by ","
-    let headers: Vec<&str> = headers.split(",").collect();
+    let headers: Vec<&str> = headers.split(',').collect();
 
     // Split values into Vector of strings split by ","
-    let values: Vec<&str> = values.split(",").collect();
-
-    // Assert headers and values are the same length
-    assert_eq!(headers.len(), values.len());
+    let values: Vec<&str> = values.split(',').collect();
+
+    // Headers and values must line up one to one
+    if headers.len() != values.len() {
+        return Err(RAFDataError::Parse {
+            path: filename.to_string(),
+            line: 2,
+            message: format!("found {} coefficient values for {} header columns", values.len(), headers.len()),
+        });
+    }
 
     // Iterate through headers and values and insert into BTreeMap
-    for i in 0..headers.len() {
+    for (header, value) in headers.iter().zip(values.iter()) {
 
-        let mut key = headers[i].trim().to_string();
-        key = key.replace("\"", "");
+        let key = header.trim().replace('"', "");
+        let coefficient = value.trim().parse::<f32>().map_err(|err| RAFDataError::Parse {
+            path: filename.to_string(),
+            line: 2,
+            message: format!("invalid coefficient '{}' for {}: {}", value.trim(), key, err),
+        })?;
 
-        map.insert(key, values[i].trim().parse::<f32>().unwrap());
+        map.insert(key, coefficient);
     }
     Ok(map)
 }
@@ -101,59 +153,164 @@ pub fn read_hcc_coefficients(filename: &str) -> Result<BTreeMap<String, f32>, cs
     Reads in a CSV file and returns a dictionary of diagnosis codes to a list of 
     HCCs (hierarchical condition categories)
 */
-pub fn read_dx_to_cc(filename: &str) -> Result<BTreeMap<String, Vec<String>>, csv::Error> {
+pub fn read_dx_to_cc(filename: &str) -> Result<BTreeMap<String, Vec<String>>, RAFDataError> {
 
-    let file = File::open(filename)?;
+    let file = open_data_file(filename)?;
     let mut reader = ReaderBuilder::new()
         .has_headers(false)
         .delimiter(b'\t')
-        .from_reader(BufReader::new(file));
+        .from_reader(file);
     let mut map = BTreeMap::<String, Vec<String>>::new();
     for result in reader.records() {
-        let record = result?;
-        let dx = &mut record[0].to_string();
-        let cc = &mut record[1].to_string();
+        let record = result.map_err(|source| RAFDataError::Csv {
+            path: filename.to_string(),
+            line: source.position().map(|p| p.line()),
+            source,
+        })?;
+        let line = record.position().map_or(0, |p| p.line() as usize);
+        let dx = record.get(0).unwrap_or("").trim().to_string();
+        let cc = record.get(1).unwrap_or("").trim();
+        if dx.is_empty() || cc.is_empty() || !cc.chars().all(|c| c.is_ascii_digit()) {
+            return Err(RAFDataError::Parse {
+                path: filename.to_string(),
+                line,
+                message: format!("expected a diagnosis code and a numeric CC, found {:?}", record),
+            });
+        }
         // Append HCC to condition category to match the format in the HCC coefficients file 
-        cc.insert_str(0, "HCC");
+        let cc = "HCC".to_owned() + cc;
 
         // If the diagnosis code is already in the dictionary, append the new condition category to the existing value
-        if map.contains_key(dx) {
-            map.get_mut(dx).unwrap().push(cc.to_string());
-        } else {
-            map.insert(dx.to_string(), vec![cc.to_string()]);
-        }
+        map.entry(dx).or_default().push(cc);
+    }
+
+    if map.is_empty() {
+        return Err(RAFDataError::Empty { path: filename.to_string() });
     }
     Ok(map)
 }
 
-pub fn build_ne_reg_variable_list() -> Vec<String> {
+/*
+    Parses the model definition out of CMS SAS programs, given as (path, text) pairs in the
+    order SAS includes them, e.g. V2823T2P (which defines &HCCV28_list115) then V2823T2M.
+    Every %LET whose value is a list of names and &references is kept, a later definition
+    replacing an earlier one, and each %SCOREMAC call names the list summed into a score.
+*/
+pub fn parse_model_definition(sources: &[(&str, &str)]) -> Result<ModelDefinition, RAFDataError> {
+
+    let let_re = Regex::new(r"(?i)%LET\s+(\w+)\s*=([^;]*);").unwrap();
+    let str_re = Regex::new(r"(?is)^\s*%STR\((.*)\)\s*$").unwrap();
+    let token_re = Regex::new(r"^&?[A-Za-z_]\w*$").unwrap();
+    let scoremac_re = Regex::new(r"(?i)%&?SCOREMAC\(\s*PVAR\s*=\s*(\w+)\s*,\s*RLIST\s*=\s*&(\w+)\s*,\s*CPREF\s*=\s*(\w+)\s*\)").unwrap();
+    let line_of = |text: &str, offset: usize| text[..offset].matches('\n').count() + 1;
+
+    let mut raw = BTreeMap::<String, Vec<String>>::new();
+    let mut calls = vec![];
+    for (path, text) in sources {
+        for caps in let_re.captures_iter(text) {
+            let value = caps.get(2).unwrap().as_str();
+            let value = str_re.captures(value).map(|x| x.get(1).unwrap().as_str()).unwrap_or(value);
+            let tokens: Vec<String> = value.split_whitespace().map(|x| x.to_string()).collect();
+            if tokens.iter().all(|x| token_re.is_match(x)) {
+                raw.insert(caps[1].to_uppercase(), tokens);
+            }
+        }
+        for caps in scoremac_re.captures_iter(text) {
+            let line = line_of(text, caps.get(0).unwrap().start());
+            let prefix = caps[3].trim_end_matches('_').to_string();
+            calls.push((*path, line, caps[1].to_string(), caps[2].to_uppercase(), prefix));
+        }
+    }
 
-    let mut ne_reg_variables = vec![];
+    // Lists referring to something that is not a list (e.g. a macro parameter) are left out
+    let mut lists = BTreeMap::new();
+    for name in raw.keys() {
+        if let Ok(variables) = _expand_sas_list(&raw, name, 0) {
+            lists.insert(name.clone(), variables);
+        }
+    }
 
-    let le_65_age_segments = vec!["NEF0_34", "NEF35_44", "NEF45_54", "NEF55_59", "NEF60_64", "NEM0_34", "NEM35_44", "NEM45_54", "NEM55_59", "NEM60_64"];
-    let ge_65_age_segments = vec!["NEF65", "NEF66", "NEF67", "NEF68", "NEF69", "NEF70_74", "NEF75_79", "NEF80_84", "NEF85_89", "NEF90_94", "NEF95_GT", 
-                                             "NEM65", "NEM66", "NEM67", "NEM68", "NEM69", "NEM70_74", "NEM75_79", "NEM80_84", "NEM85_89", "NEM90_94", "NEM95_GT"];
+    let mut scores = vec![];
+    for (path, line, score, list, prefix) in calls {
+        let variables = _expand_sas_list(&raw, &list, 0).map_err(|message| RAFDataError::Parse {
+            path: path.to_string(),
+            line,
+            message: format!("RLIST of {}: {}", score, message),
+        })?;
+        scores.push(ScoreDefinition { score, prefix, list, variables });
+    }
 
+    if scores.is_empty() {
+        return Err(RAFDataError::Empty { path: sources.last().map(|x| x.0).unwrap_or("").to_string() });
+    }
+    Ok(ModelDefinition { lists, scores })
+}
 
-    let left_permutations = vec!["NMCAID", "MCAID"];
-    let right_permutations = vec!["ORIGDS", "NORIGDS"];
+// Expands the &references of a %LET list, failing on undefined or recursive lists
+fn _expand_sas_list(raw: &BTreeMap<String, Vec<String>>, name: &str, depth: usize) -> Result<Vec<String>, String> {
 
-    for age_group in ge_65_age_segments {
-        for left_perm in &left_permutations {
-            for right_perm in &right_permutations {
-                ne_reg_variables.push(format!("{}_{}_{}", left_perm, right_perm, age_group));
-            }
+    if depth > 16 {
+        return Err(format!("&{} is defined recursively", name));
+    }
+    let tokens = raw.get(name).ok_or(format!("&{} is not a defined list", name))?;
+    let mut variables = vec![];
+    for token in tokens {
+        match token.strip_prefix('&') {
+            Some(reference) => variables.extend(_expand_sas_list(raw, &reference.to_uppercase(), depth + 1)?),
+            None => variables.push(token.clone()),
         }
     }
+    Ok(variables)
+}
+
+/*
+    Reads the CMS SAS programs in the order SAS includes them and parses the model definition
+*/
+pub fn read_model_definition(filenames: &[&str]) -> Result<ModelDefinition, RAFDataError> {
 
-    for age_group in le_65_age_segments {
-        for left_perm in &left_permutations {
-            ne_reg_variables.push(format!("{}_NORIGDS_{}", left_perm, age_group));
+    let mut texts = vec![];
+    for filename in filenames {
+        match fs::read(filename) {
+            Ok(bytes) => texts.push(String::from_utf8_lossy(&bytes).to_string()),
+            Err(source) => return Err(RAFDataError::Open { path: filename.to_string(), source }),
         }
     }
+    let sources: Vec<(&str, &str)> = filenames.iter().copied().zip(texts.iter().map(|x| x.as_str())).collect();
+    parse_model_definition(&sources)
+}
+
+// Performance year whose CMS files the Host scores with, see `get_cms_data_dir`
+pub const PERFORMANCE_YEAR: &str = "PY2023";
 
-    return ne_reg_variables;
+// CMS-HCC model software the files of `PUBLIC_INPUT_FILES` belong to
+pub const MODEL_VERSION: &str = "V28 (V2823.115.T2)";
 
+// Files read by `load_public_inputs`: labels, hierarchies, coefficients, dx_to_cc and the
+// two SAS macro files of the model
+pub const PUBLIC_INPUT_FILES: [&str; 6] = ["V28115L3.TXT", "V28115H1.TXT", "C2824T2N.csv", "F2823T2N_FY22FY23.TXT", "V2823T2P.TXT", "V2823T2M.TXT"];
+
+/*
+    Reads every CMS table needed by the Guest from the given performance year directory.
+    Any missing or malformed file is returned as an error rather than an empty table.
+ */
+pub fn load_public_inputs(cms_dir: &str) -> Result<PublicRAFInputs, RAFDataError> {
+
+    let [labels, hierarchies, coefficients, dx_to_cc, model_p, model_m] = PUBLIC_INPUT_FILES.map(|x| format!("{}/{}", cms_dir, x));
+    let hcc_labels = read_hcc_labels(&labels)?;
+    let hcc_hiers = read_hier(&hierarchies)?;
+    let hcc_coeffs = read_hcc_coefficients(&coefficients)?;
+    let dx_to_cc = read_dx_to_cc(&dx_to_cc)?;
+    let model = read_model_definition(&[&model_p, &model_m])?;
+
+    Ok(PublicRAFInputs {
+        hcc_coefficients: hcc_coeffs,
+        hcc_hierarchies: hcc_hiers,
+        hcc_labels,
+        dx_to_cc,
+        interactions: InteractionDefinitions::v28(),
+        model,
+        norm_factor: 1.0,
+    })
 }
 
 #[test]
@@ -171,7 +328,7 @@ fn can_locate_cms_data_dir() {
 #[test]
 fn can_build_hcc_labels_from_file() {
     let path = get_cms_data_dir("PY2023");
-    let filename = path + "/V28115L3.txt";
+    let filename = path + "/V28115L3.TXT";
     let labels = read_hcc_labels(&filename).unwrap();
     assert_eq!(labels.len(), 230);
     assert_eq!("HIV/AIDS ", labels.get("HCC1").unwrap());
@@ -245,7 +402,83 @@ fn can_build_dx_to_cc_from_file() {
 
 #[test]
 fn can_build_ne_reg_variables() {
-    let ne_reg_variables = build_ne_reg_variable_list();
+    let model = load_public_inputs(&get_cms_data_dir("PY2023")).unwrap().model;
+    let ne_reg_variables = model.list("NE_REG").unwrap().to_vec();
     assert!(!ne_reg_variables.contains(&"MCAID_ORIGDS_NEF0_34".to_string()));
+    assert!(!ne_reg_variables.contains(&"MCAID_ORIGDIS_NEF0_34".to_string()));
+    assert!(ne_reg_variables.contains(&"MCAID_ORIGDIS_NEF65".to_string()));
+    assert!(ne_reg_variables.contains(&"NMCAID_NORIGDIS_NEM0_34".to_string()));
     assert_eq!(ne_reg_variables.len(), 108);
-}
\ No newline at end of file
+
+    // Every cell of &NE_REG has a published NE and SNPNE coefficient
+    let hcc_coefficients = read_hcc_coefficients(&(get_cms_data_dir("PY2023") + "/C2824T2N.csv")).unwrap();
+    for variable in &ne_reg_variables {
+        assert!(hcc_coefficients.contains_key(&format!("NE_{}", variable)), "NE_{}", variable);
+        assert!(hcc_coefficients.contains_key(&format!("SNPNE_{}", variable)), "SNPNE_{}", variable);
+    }
+}
+
+#[test]
+fn can_parse_model_definition_from_sas_macros() {
+    let path = get_cms_data_dir("PY2023");
+    let model = read_model_definition(&[&(path.clone() + "/V2823T2P.TXT"), &(path + "/V2823T2M.TXT")]).unwrap();
+
+    assert_eq!(model.payment_hccs().len(), 115);
+    assert_eq!(model.list("HCCV28_list115"), Some(model.payment_hccs()));
+    assert_eq!(model.list("ADDZ").unwrap().join(" "), "D1 D2 D3 D4 D5 D6 D7 D8 D9 D10P");
+    assert_eq!(model.list("AGESEXVA").unwrap().len(), 14);
+    assert_eq!(model.list("INTERRACC_VARSA").unwrap().last().unwrap(), "HF_HCC238_V28");
+
+    // &AGESEXVA &orig_int &HCClist &INTERRACC_VARSA &ADDZ
+    let scores: Vec<(&str, &str, &str)> = model.scores.iter().map(|x| (x.score.as_str(), x.prefix.as_str(), x.list.as_str())).collect();
+    assert_eq!(scores.len(), 9);
+    assert_eq!(scores[0], ("SCORE_COMMUNITY_NA", "CNA", "COMM_REGA"));
+    assert_eq!(scores[6], ("SCORE_INSTITUTIONAL", "INS", "INST_REG"));
+    assert_eq!(scores[8], ("SCORE_SNP_NEW_ENROLLEE", "SNPNE", "NE_REG"));
+    assert_eq!(model.score("SCORE_COMMUNITY_NA").unwrap().variables.len(), 14 + 2 + 115 + 5 + 10);
+    assert_eq!(model.score("SCORE_INSTITUTIONAL").unwrap().variables.len(), 24 + 2 + 115 + 9 + 10);
+    assert!(model.score("SCORE_COMMUNITY_ND").unwrap().variables.contains(&"gSubUseDisorder_gPsych_V28".to_string()));
+}
+
+#[test]
+fn reports_undefined_scoremac_list_with_line() {
+    let text = "%LET ADDZ=%STR(D1 D2);\n%LET COMM_REGA= %STR(&AGESEXVA &ADDZ);\n\n%&SCOREMAC(PVAR=SCORE_COMMUNITY_NA, RLIST=&COMM_REGA, CPREF=CNA_);\n";
+    let err = parse_model_definition(&[("T2M.TXT", text)]).unwrap_err();
+    assert!(matches!(err, RAFDataError::Parse { line: 4, .. }));
+    assert!(err.to_string().contains("&AGESEXVA"));
+
+    let model = parse_model_definition(&[("T2P.TXT", "%let agesexva = F65_69;"), ("T2M.TXT", text)]).unwrap();
+    assert_eq!(model.scores[0].variables, vec!["F65_69", "D1", "D2"]);
+}
+
+#[test]
+fn reports_missing_data_file() {
+    let path = get_cms_data_dir("PY2023");
+    let filename = path + "/DOES_NOT_EXIST.TXT";
+    let err = read_hcc_labels(&filename).unwrap_err();
+    assert!(matches!(err, RAFDataError::Open { .. }));
+    assert_eq!(err.path(), filename);
+}
+
+#[test]
+fn reports_malformed_coefficient_with_line() {
+    let filename = env::temp_dir().join("zero_raf_bad_coefficients.csv");
+    fs::write(&filename, "\"CNA_F65_69\",\"CNA_F70_74\"\n0.33,abc\n").unwrap();
+    let err = read_hcc_coefficients(filename.to_str().unwrap()).unwrap_err();
+    assert_eq!(err.line(), Some(2));
+    assert!(err.to_string().contains("CNA_F70_74"));
+
+    fs::write(&filename, "\"CNA_F65_69\",\"CNA_F70_74\"\n0.33\n").unwrap();
+    let err = read_hcc_coefficients(filename.to_str().unwrap()).unwrap_err();
+    assert!(matches!(err, RAFDataError::Parse { line: 2, .. }));
+    fs::remove_file(&filename).unwrap();
+}
+
+#[test]
+fn reports_malformed_dx_row_with_line() {
+    let filename = env::temp_dir().join("zero_raf_bad_dx_to_cc.txt");
+    fs::write(&filename, "A0104\t92\t\nA021\tXX\t\n").unwrap();
+    let err = read_dx_to_cc(filename.to_str().unwrap()).unwrap_err();
+    assert_eq!(err.line(), Some(2));
+    fs::remove_file(&filename).unwrap();
+}
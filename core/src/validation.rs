@@ -0,0 +1,291 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use crate::interactions::INTERACTION_FLAGS;
+use crate::PublicRAFInputs;
+
+/// A single inconsistency between the tables that make up `PublicRAFInputs`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationIssue {
+    // One of the tables has no entries at all
+    EmptyTable { table: &'static str },
+
+    // An HCC referenced by `table` (under `key`) has no entry in `hcc_labels`
+    UnknownHcc { table: &'static str, key: String, hcc: String },
+
+    // A regression variable used by `score` has no coefficient in `hcc_coefficients`
+    MissingCoefficient { score: String, coefficient: String },
+
+    // Following the hierarchies from the first HCC leads back to it
+    CyclicHierarchy { cycle: Vec<String> },
+
+    // An interaction factor is neither a diagnostic group, a model HCC nor a beneficiary flag
+    UnknownInteractionFactor { interaction: String, factor: String },
+
+    // The normalization factor would scale every score to zero, a negative or a non-number
+    InvalidNormFactor { norm_factor: f32 },
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::EmptyTable { table } => {
+                write!(f, "{} is empty", table)
+            }
+            ValidationIssue::UnknownHcc { table, key, hcc } => {
+                write!(f, "{} entry {} references {} which has no label", table, key, hcc)
+            }
+            ValidationIssue::MissingCoefficient { score, coefficient } => {
+                write!(f, "{} requires coefficient {} which is missing", score, coefficient)
+            }
+            ValidationIssue::CyclicHierarchy { cycle } => {
+                write!(f, "hcc_hierarchies contains a cycle: {}", cycle.join(" -> "))
+            }
+            ValidationIssue::UnknownInteractionFactor { interaction, factor } => {
+                write!(f, "interaction {} uses {} which is not a diagnostic group, HCC or flag", interaction, factor)
+            }
+            ValidationIssue::InvalidNormFactor { norm_factor } => {
+                write!(f, "norm_factor {} must be a positive number", norm_factor)
+            }
+        }
+    }
+}
+
+impl PublicRAFInputs {
+
+    /// Model HCCs (&HCClist), i.e. the `HCC` entries of `hcc_labels`
+    pub fn model_hccs(&self) -> BTreeSet<&str> {
+        self.hcc_labels
+            .keys()
+            .filter(|k| k.starts_with("HCC"))
+            .map(|k| k.as_str())
+            .collect()
+    }
+
+    /// Checks the public tables against each other and returns every inconsistency found,
+    /// so a bad CMS release can be fixed in one pass rather than one error at a time.
+    pub fn validate(&self) -> Result<(), Vec<ValidationIssue>> {
+
+        let mut issues = vec![];
+        let model_hccs = self.model_hccs();
+
+        if self.hcc_coefficients.is_empty() {
+            issues.push(ValidationIssue::EmptyTable { table: "hcc_coefficients" });
+        }
+        if self.hcc_hierarchies.is_empty() {
+            issues.push(ValidationIssue::EmptyTable { table: "hcc_hierarchies" });
+        }
+        if model_hccs.is_empty() {
+            issues.push(ValidationIssue::EmptyTable { table: "hcc_labels" });
+        }
+        if self.dx_to_cc.is_empty() {
+            issues.push(ValidationIssue::EmptyTable { table: "dx_to_cc" });
+        }
+        if self.model.scores.is_empty() {
+            issues.push(ValidationIssue::EmptyTable { table: "model" });
+        }
+        if !(self.norm_factor.is_finite() && self.norm_factor > 0.0) {
+            issues.push(ValidationIssue::InvalidNormFactor { norm_factor: self.norm_factor });
+        }
+
+        // Every HCC a diagnosis maps to must be a model HCC
+        for (dx, hccs) in &self.dx_to_cc {
+            for hcc in hccs {
+                if !model_hccs.contains(hcc.as_str()) {
+                    issues.push(ValidationIssue::UnknownHcc { table: "dx_to_cc", key: dx.clone(), hcc: hcc.clone() });
+                }
+            }
+        }
+
+        // Every hierarchy key and child must be a model HCC
+        for (superior, inferiors) in &self.hcc_hierarchies {
+            if !model_hccs.contains(superior.as_str()) {
+                issues.push(ValidationIssue::UnknownHcc { table: "hcc_hierarchies", key: superior.clone(), hcc: superior.clone() });
+            }
+            for inferior in inferiors {
+                if !model_hccs.contains(inferior.as_str()) {
+                    issues.push(ValidationIssue::UnknownHcc { table: "hcc_hierarchies", key: superior.clone(), hcc: inferior.clone() });
+                }
+            }
+        }
+
+        for cycle in find_hierarchy_cycles(&self.hcc_hierarchies) {
+            issues.push(ValidationIssue::CyclicHierarchy { cycle });
+        }
+
+        // Diagnostic groups are made of model HCCs, interactions of groups, HCCs and flags
+        for (group, members) in &self.interactions.groups {
+            for hcc in members {
+                if !model_hccs.contains(hcc.as_str()) {
+                    issues.push(ValidationIssue::UnknownHcc { table: "diagnostic_groups", key: group.clone(), hcc: hcc.clone() });
+                }
+            }
+        }
+        for (interaction, factors) in &self.interactions.interactions {
+            for factor in factors {
+                let known = self.interactions.groups.contains_key(factor)
+                    || model_hccs.contains(factor.as_str())
+                    || INTERACTION_FLAGS.contains(&factor.as_str());
+                if !known {
+                    issues.push(ValidationIssue::UnknownInteractionFactor { interaction: interaction.clone(), factor: factor.clone() });
+                }
+            }
+        }
+
+        // The payment HCCs of the model (&HCClist) must all be labelled
+        for hcc in self.model.payment_hccs() {
+            if !model_hccs.contains(hcc.as_str()) {
+                issues.push(ValidationIssue::UnknownHcc { table: "model", key: "HCClist".to_string(), hcc: hcc.clone() });
+            }
+        }
+
+        // Every regression variable needs a coefficient for each score that uses it
+        for score in &self.model.scores {
+            for variable in &score.variables {
+                let coefficient = format!("{}_{}", score.prefix, variable);
+                if !self.hcc_coefficients.contains_key(&coefficient) {
+                    issues.push(ValidationIssue::MissingCoefficient { score: score.score.clone(), coefficient });
+                }
+            }
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+}
+
+/*
+    Depth first search over the hierarchy graph returning each distinct cycle once,
+    as the list of HCCs starting and ending with the same HCC.
+*/
+pub fn find_hierarchy_cycles(hiers: &BTreeMap<String, Vec<String>>) -> Vec<Vec<String>> {
+
+    // 0 = unvisited, 1 = on the current path, 2 = finished
+    let mut state = BTreeMap::<&str, u8>::new();
+    let mut cycles = vec![];
+
+    for start in hiers.keys() {
+        if state.contains_key(start.as_str()) {
+            continue;
+        }
+        let mut path: Vec<&str> = vec![];
+        let mut stack: Vec<(&str, usize)> = vec![(start.as_str(), 0)];
+        state.insert(start.as_str(), 1);
+        path.push(start.as_str());
+
+        while let Some((node, child_index)) = stack.pop() {
+            let children = hiers.get(node).map(|v| v.as_slice()).unwrap_or(&[]);
+            if child_index < children.len() {
+                stack.push((node, child_index + 1));
+                let child = children[child_index].as_str();
+                match state.get(child) {
+                    None => {
+                        state.insert(child, 1);
+                        path.push(child);
+                        stack.push((child, 0));
+                    }
+                    Some(1) => {
+                        let begin = path.iter().position(|x| *x == child).unwrap();
+                        let mut cycle: Vec<String> = path[begin..].iter().map(|x| x.to_string()).collect();
+                        cycle.push(child.to_string());
+                        cycles.push(cycle);
+                    }
+                    _ => {}
+                }
+            } else {
+                state.insert(node, 2);
+                path.pop();
+            }
+        }
+    }
+
+    cycles
+}
+
+#[cfg(test)]
+fn _small_public_inputs() -> PublicRAFInputs {
+    use crate::interactions::InteractionDefinitions;
+    use crate::utils::parse_model_definition;
+    let sas = "
+        %LET HCClist=%STR(HCC17 HCC18 HCC19);
+        %LET COMM_REGA=%STR(F65_69 &HCClist D1);
+        %LET INST_REG=%STR(LTIMCAID ORIGDS &HCClist);
+        %&SCOREMAC(PVAR=SCORE_COMMUNITY_NA, RLIST=&COMM_REGA, CPREF=CNA_);
+        %&SCOREMAC(PVAR=SCORE_COMMUNITY_ND, RLIST=&COMM_REGA, CPREF=CND_);
+        %&SCOREMAC(PVAR=SCORE_INSTITUTIONAL, RLIST=&INST_REG, CPREF=INS_);";
+    let model = parse_model_definition(&[("V2823T2M.TXT", sas)]).unwrap();
+
+    let mut hcc_labels = BTreeMap::new();
+    let mut hcc_coefficients = BTreeMap::new();
+    for hcc in ["HCC17", "HCC18", "HCC19"] {
+        hcc_labels.insert(hcc.to_string(), format!("{} label", hcc));
+        hcc_labels.insert(hcc.replace("HCC", "CC"), format!("{} label", hcc));
+    }
+    for score in &model.scores {
+        for variable in &score.variables {
+            hcc_coefficients.insert(format!("{}_{}", score.prefix, variable), 0.1);
+        }
+    }
+    let mut hcc_hierarchies = BTreeMap::new();
+    hcc_hierarchies.insert("HCC17".to_string(), vec!["HCC18".to_string(), "HCC19".to_string()]);
+    hcc_hierarchies.insert("HCC18".to_string(), vec!["HCC19".to_string()]);
+    let mut dx_to_cc = BTreeMap::new();
+    dx_to_cc.insert("C7800".to_string(), vec!["HCC17".to_string()]);
+    let mut interactions = InteractionDefinitions::default();
+    interactions.groups.insert("CANCER".to_string(), vec!["HCC17".to_string(), "HCC18".to_string()]);
+    interactions.interactions.insert("DISABLED_CANCER".to_string(), vec!["DISABL".to_string(), "CANCER".to_string()]);
+
+    PublicRAFInputs { hcc_coefficients, hcc_hierarchies, hcc_labels, dx_to_cc, interactions, model, norm_factor: 1.0 }
+}
+
+#[test]
+fn accepts_consistent_public_inputs() {
+    assert_eq!(_small_public_inputs().validate(), Ok(()));
+}
+
+#[test]
+fn reports_every_inconsistency() {
+    let mut inputs = _small_public_inputs();
+    inputs.dx_to_cc.insert("Z9999".to_string(), vec!["HCC999".to_string()]);
+    inputs.hcc_hierarchies.insert("HCC19".to_string(), vec!["HCC17".to_string()]);
+    inputs.hcc_coefficients.remove("INS_LTIMCAID");
+    inputs.hcc_coefficients.remove("CND_HCC18");
+    inputs.interactions.groups.get_mut("CANCER").unwrap().push("HCC20".to_string());
+    inputs.interactions.interactions.insert("CANCER_LUNG".to_string(), vec!["CANCER".to_string(), "LUNG".to_string()]);
+    inputs.model.lists.get_mut("HCCLIST").unwrap().push("HCC999".to_string());
+
+    let issues = inputs.validate().unwrap_err();
+    assert!(issues.contains(&ValidationIssue::UnknownHcc {
+        table: "dx_to_cc", key: "Z9999".to_string(), hcc: "HCC999".to_string() }));
+    assert!(issues.contains(&ValidationIssue::MissingCoefficient {
+        score: "SCORE_INSTITUTIONAL".to_string(), coefficient: "INS_LTIMCAID".to_string() }));
+    assert!(issues.contains(&ValidationIssue::MissingCoefficient {
+        score: "SCORE_COMMUNITY_ND".to_string(), coefficient: "CND_HCC18".to_string() }));
+    assert!(issues.contains(&ValidationIssue::UnknownHcc {
+        table: "model", key: "HCClist".to_string(), hcc: "HCC999".to_string() }));
+    assert!(issues.iter().any(|issue| matches!(issue, ValidationIssue::CyclicHierarchy { .. })));
+    assert!(issues.contains(&ValidationIssue::UnknownHcc {
+        table: "diagnostic_groups", key: "CANCER".to_string(), hcc: "HCC20".to_string() }));
+    assert!(issues.contains(&ValidationIssue::UnknownInteractionFactor {
+        interaction: "CANCER_LUNG".to_string(), factor: "LUNG".to_string() }));
+    assert_eq!(issues.len(), 7);
+}
+
+#[test]
+fn validates_published_py2023_tables() {
+    use crate::interactions::InteractionDefinitions;
+    use crate::utils::{get_cms_data_dir, read_dx_to_cc, read_hcc_coefficients, read_hcc_labels, read_hier, read_model_definition};
+    let path = get_cms_data_dir("PY2023");
+    let inputs = PublicRAFInputs {
+        hcc_coefficients: read_hcc_coefficients(&(path.clone() + "/C2824T2N.csv")).unwrap(),
+        hcc_hierarchies: read_hier(&(path.clone() + "/V28115H1.TXT")).unwrap(),
+        hcc_labels: read_hcc_labels(&(path.clone() + "/V28115L3.TXT")).unwrap(),
+        dx_to_cc: read_dx_to_cc(&(path.clone() + "/F2823T2N_FY22FY23.TXT")).unwrap(),
+        interactions: InteractionDefinitions::v28(),
+        model: read_model_definition(&[&(path.clone() + "/V2823T2P.TXT"), &(path + "/V2823T2M.TXT")]).unwrap(),
+        norm_factor: 1.0,
+    };
+    assert_eq!(inputs.validate(), Ok(()));
+}
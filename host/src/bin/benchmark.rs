@@ -0,0 +1,167 @@
+use zero_raf_core::PrivateRAFInput;
+use zero_raf_core::bench::{accumulate_phases, find_cycle_regressions, phase_cycles, BenchmarkCase, BenchmarkReport, PhaseCycles, BENCHMARK_REPORT_VERSION};
+use zero_raf_core::compiled::CompiledRAFTables;
+use zero_raf_core::dx_table::DxMerkleTable;
+use zero_raf_core::interactions::InteractionDefinitions;
+use zero_raf_core::merkle::Sha2Hasher;
+use zero_raf_core::synthetic::{PopulationProfile, ProfileError, SyntheticPopulation};
+use zero_raf_core::utils::{get_cms_data_dir, load_public_inputs, PERFORMANCE_YEAR};
+use zero_raf_host::proving::{execute_member_logged, ScoringTables};
+use zero_raf_methods::ZERO_RAF_ID;
+use risc0_zkvm::Session;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::time::Instant;
+
+/*
+    Runs the Guest over synthetic rosters of varying size and diagnosis count and writes
+    a JSON report of cycles per phase, segments and execution / proving wall time.
+
+    Usage: benchmark [--members 1,10] [--dx 0,5,20] [--seed 42] [--prove]
+                     [--out zero-raf-benchmark.json] [--baseline previous.json] [--tolerance 0.05]
+
+    With --baseline the run fails when the cycles per member of any case grew by more
+    than the tolerance, so it can gate changes to the Guest logic.
+*/
+struct BenchmarkArgs {
+    members: Vec<usize>,
+    dx_per_member: Vec<usize>,
+    seed: u64,
+    prove: bool,
+    out: String,
+    baseline: Option<String>,
+    tolerance: f64,
+}
+
+fn _parse_list(value: &str) -> Result<Vec<usize>, Box<dyn Error>> {
+    let mut list = vec![];
+    for x in value.split(',') {
+        list.push(x.trim().parse::<usize>()?);
+    }
+    Ok(list)
+}
+
+fn parse_args() -> Result<BenchmarkArgs, Box<dyn Error>> {
+
+    let mut args = BenchmarkArgs {
+        members: vec![1, 10],
+        dx_per_member: vec![0, 5, 20],
+        seed: 42,
+        prove: false,
+        out: "zero-raf-benchmark.json".to_string(),
+        baseline: None,
+        tolerance: 0.05,
+    };
+
+    let mut argv = std::env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        let mut value = || argv.next().ok_or(format!("{} expects a value", arg));
+        match arg.as_str() {
+            "--members" => args.members = _parse_list(&value()?)?,
+            "--dx" => args.dx_per_member = _parse_list(&value()?)?,
+            "--seed" => args.seed = value()?.parse()?,
+            "--prove" => args.prove = true,
+            "--out" => args.out = value()?,
+            "--baseline" => args.baseline = Some(value()?),
+            "--tolerance" => args.tolerance = value()?.parse()?,
+            _ => return Err(format!("unknown argument {}", arg).into()),
+        }
+    }
+    Ok(args)
+}
+
+/*
+    Builds `members` synthetic beneficiaries with `dx_per_member` diagnosis codes each, drawn
+    from the real dx_to_cc table. Roughly one code in ten is not in dx_to_cc so the unmapped
+    path is measured as well.
+*/
+fn synthetic_roster(members: usize, dx_per_member: usize, seed: u64, dx_to_cc: &BTreeMap<String, Vec<String>>) -> Result<Vec<PrivateRAFInput>, ProfileError> {
+    let profile = PopulationProfile { dx_counts: vec![(dx_per_member, 1)], ..PopulationProfile::default() };
+    return Ok(SyntheticPopulation::new(dx_to_cc, profile, seed)?.roster(members));
+}
+
+// Executes the Guest for one member, returning the session and the Guest stdout
+fn execute_member(private_input: &PrivateRAFInput, table_words: &[u32], interactions: &InteractionDefinitions, dx_table: &DxMerkleTable) -> (Session, String) {
+
+    // Ask the Guest for the phase markers `phase_cycles` reads
+    let mut stdout = vec![];
+    let tables = ScoringTables { words: table_words, dx_table, interactions };
+    let session = execute_member_logged(tables, private_input, None, Some(&mut stdout)).unwrap();
+    return (session, String::from_utf8_lossy(&stdout).to_string());
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+
+    let args = parse_args()?;
+
+    let cms_dir = get_cms_data_dir(PERFORMANCE_YEAR);
+    let public_inputs = load_public_inputs(&cms_dir)?;
+    let table_words = CompiledRAFTables::compile(&public_inputs)?.to_words();
+    let dx_table = DxMerkleTable::from_dx_to_cc::<Sha2Hasher>(&public_inputs.dx_to_cc);
+
+    let mut cases = vec![];
+    for &members in &args.members {
+        for &dx_per_member in &args.dx_per_member {
+
+            let name = format!("members={},dx={}", members, dx_per_member);
+            let roster = synthetic_roster(members, dx_per_member, args.seed, &public_inputs.dx_to_cc)?;
+
+            let mut segments = 0;
+            let mut total_cycles = 0;
+            let mut phases: Vec<PhaseCycles> = vec![];
+            let mut execute_ms = 0;
+            let mut prove_ms = if args.prove { Some(0) } else { None };
+
+            for private_input in &roster {
+                let started = Instant::now();
+                let (session, stdout) = execute_member(private_input, &table_words, &public_inputs.interactions, &dx_table);
+                execute_ms += started.elapsed().as_millis() as u64;
+
+                let member_cycles: u64 = session.resolve().unwrap().iter().map(|x| x.insn_cycles as u64).sum();
+                segments += session.segments.len();
+                total_cycles += member_cycles;
+                accumulate_phases(&mut phases, &phase_cycles(&stdout, member_cycles));
+
+                if let Some(prove_ms) = prove_ms.as_mut() {
+                    let started = Instant::now();
+                    let receipt = session.prove().unwrap();
+                    *prove_ms += started.elapsed().as_millis() as u64;
+                    receipt.verify(ZERO_RAF_ID).expect("Benchmark receipts should verify");
+                }
+            }
+
+            let case = BenchmarkCase { name, members, dx_per_member, segments, total_cycles, phases, execute_ms, prove_ms };
+            println!("{}: {} cycles per member, {} segments, execute {} ms, prove {}",
+                     case.name, case.cycles_per_member(), case.segments, case.execute_ms,
+                     case.prove_ms.map(|x| format!("{} ms", x)).unwrap_or("skipped".to_string()));
+            for phase in &case.phases {
+                println!("    {:>12}  {}", phase.cycles, phase.phase);
+            }
+            cases.push(case);
+        }
+    }
+
+    let report = BenchmarkReport {
+        version: BENCHMARK_REPORT_VERSION,
+        image_id: ZERO_RAF_ID.iter().map(|x| format!("{:08x}", x)).collect(),
+        seed: args.seed,
+        cases,
+    };
+    fs::write(&args.out, serde_json::to_string_pretty(&report)?)?;
+    println!("Wrote benchmark report to {}", args.out);
+
+    if let Some(baseline_path) = &args.baseline {
+        let baseline: BenchmarkReport = serde_json::from_str(&fs::read_to_string(baseline_path)?)?;
+        let regressions = find_cycle_regressions(&baseline, &report, args.tolerance);
+        if !regressions.is_empty() {
+            eprintln!("Cycle regressions against {}:", baseline_path);
+            for regression in &regressions {
+                eprintln!("  - {}", regression);
+            }
+            std::process::exit(1);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use zero_raf_core::compiled::{tables_digest, CompiledRAFTables, CompiledTablesView};
+use zero_raf_core::dx_table::DxMerkleTable;
+use zero_raf_core::merkle::{hash_to_hex, Sha2Hasher};
+use zero_raf_core::population::{member_score_proofs, score_population_with_results, PopulationJournal, RosterMember};
+use zero_raf_core::synthetic::{PopulationProfile, SyntheticPopulation, SyntheticRng};
+use zero_raf_core::utils::{get_cms_data_dir, load_public_inputs, PERFORMANCE_YEAR};
+use zero_raf_host::salts::{random_salt, write_salts, MemberSalt};
+use zero_raf_methods::{ZERO_RAF_POPULATION_ELF, ZERO_RAF_POPULATION_ID};
+use risc0_zkvm::serde::{from_slice, to_vec};
+use risc0_zkvm::{Executor, ExecutorEnv};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/*
+    Proves the population risk of a roster with the population Guest, which commits only
+    aggregates (members, member months, total, mean and member months weighted mean per
+    eligibility segment, with segments below `MIN_CELL_SIZE` members suppressed) and a Merkle
+    root of the member results. The inclusion proof of each
+    member result is written next to the aggregates, so a single member's score can be
+    disclosed and checked against the receipt. The salts of the member commitments come from
+    the OS CSPRNG and are written to a separate, owner-only file that must stay with the plan.
+    Until roster files are read the roster is synthetic.
+
+    Usage: population [--members 100] [--seed 42] [--execute] [--out zero-raf-population.json]
+                      [--proofs zero-raf-population-proofs.json] [--salts zero-raf-population-salts.json]
+
+    With --execute the Guest is only executed and the aggregates are not backed by a receipt.
+*/
+struct PopulationArgs {
+    members: usize,
+    seed: u64,
+    execute_only: bool,
+    out: String,
+    proofs: String,
+    salts: String,
+}
+
+fn parse_args() -> Result<PopulationArgs, Box<dyn Error>> {
+
+    let mut args = PopulationArgs {
+        members: 100,
+        seed: 42,
+        execute_only: false,
+        out: "zero-raf-population.json".to_string(),
+        proofs: "zero-raf-population-proofs.json".to_string(),
+        salts: "zero-raf-population-salts.json".to_string(),
+    };
+
+    let mut argv = std::env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        let mut value = || argv.next().ok_or(format!("{} expects a value", arg));
+        match arg.as_str() {
+            "--members" => args.members = value()?.parse()?,
+            "--seed" => args.seed = value()?.parse()?,
+            "--execute" => args.execute_only = true,
+            "--out" => args.out = value()?,
+            "--proofs" => args.proofs = value()?,
+            "--salts" => args.salts = value()?,
+            _ => return Err(format!("unknown argument {}", arg).into()),
+        }
+    }
+    Ok(args)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+
+    let args = parse_args()?;
+
+    let public_inputs = load_public_inputs(&get_cms_data_dir(PERFORMANCE_YEAR))?;
+    let table_words = CompiledRAFTables::compile(&public_inputs)?.to_words();
+    let dx_table = DxMerkleTable::from_dx_to_cc::<Sha2Hasher>(&public_inputs.dx_to_cc);
+
+    // Most members are enrolled the whole year, the rest join or leave during it. The salts
+    // are secret, so they are not derived from the public seed.
+    let mut rng = SyntheticRng::new(args.seed);
+    let mut roster: Vec<RosterMember> = vec![];
+    for input in SyntheticPopulation::new(&public_inputs.dx_to_cc, PopulationProfile::default(), args.seed)?.roster(args.members) {
+        let member_months = if rng.percent(80) { 12 } else { 1 + rng.below(11) as u32 };
+        roster.push(RosterMember::new(input, random_salt()?, member_months, &dx_table));
+    }
+
+    let env = ExecutorEnv::builder()
+                .add_input(&to_vec(&table_words).unwrap())
+                .add_input(&to_vec(&dx_table.commitment()).unwrap())
+                .add_input(&to_vec(&public_inputs.interactions).unwrap())
+                .add_input(&to_vec(&roster).unwrap())
+                .build();
+    let mut exec = Executor::from_elf(env, ZERO_RAF_POPULATION_ELF)?;
+    let session = exec.run()?;
+    println!("Executed population guest for {} members in {} segments", roster.len(), session.segments.len());
+
+    let journal: PopulationJournal = if args.execute_only {
+        println!("UNPROVEN: --execute skips proving, these aggregates are not backed by a receipt");
+        from_slice(&session.journal)?
+    } else {
+        let receipt = session.prove()?;
+        receipt.verify(ZERO_RAF_POPULATION_ID)?;
+        fs::write("zero-raf-population-receipt.txt", serde_json::to_string(&receipt)?)?;
+        println!("Proved and verified the population receipt");
+        from_slice(&receipt.journal)?
+    };
+
+    println!("Tables digest: {}", hash_to_hex(&journal.tables_digest));
+    println!("{} members, {} member months, mean {:.4}, weighted mean {:.4}",
+             journal.total.members, journal.total.member_months, journal.total.mean_score, journal.total.weighted_mean_score);
+    for (segment, aggregate) in &journal.segments {
+        println!("    {:>5}  {:>6} members  mean {:.4}  weighted mean {:.4}",
+                 segment, aggregate.members, aggregate.mean_score, aggregate.weighted_mean_score);
+    }
+    fs::write(&args.out, serde_json::to_string_pretty(&journal)?)?;
+    println!("Wrote population aggregates to {}", args.out);
+
+    // The member results stay on the Host, only their root is in the journal
+    let (_, results) = score_population_with_results::<Sha2Hasher>(CompiledTablesView::new(&table_words)?, tables_digest::<Sha2Hasher>(&table_words),
+                                                                   &public_inputs.interactions, &dx_table.commitment(), &roster)
+        .map_err(|e| e.to_string())?;
+    let proofs = member_score_proofs::<Sha2Hasher>(&results);
+    if !proofs.iter().all(|x| x.verify::<Sha2Hasher>(&journal)) {
+        return Err("member results do not match the results root of the journal".into());
+    }
+    fs::write(&args.proofs, serde_json::to_string_pretty(&proofs)?)?;
+    println!("Results root {}, wrote {} member inclusion proofs to {}", hash_to_hex(&journal.results_root), proofs.len(), args.proofs);
+
+    // The plan keeps these to open the member commitments, they are never shared with the proofs
+    let salts: Vec<MemberSalt> = results
+        .iter()
+        .zip(&roster)
+        .map(|(result, member)| MemberSalt { input_commitment: result.member, salt: member.salt })
+        .collect();
+    write_salts(Path::new(&args.salts), &salts)?;
+    println!("Wrote the private member salts to {}, keep this file with the plan", args.salts);
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use zero_raf_core::compiled::CompiledRAFTables;
+use zero_raf_core::diagnosis::normalize_diagnosis_codes;
+use zero_raf_core::dx_table::DxMerkleTable;
+use zero_raf_core::merkle::{hash_to_hex, Sha2Hasher};
+use zero_raf_core::radv::{input_commitment, HccPresenceJournal};
+use zero_raf_core::synthetic::{PopulationProfile, SyntheticPopulation};
+use zero_raf_core::utils::{get_cms_data_dir, load_public_inputs, PERFORMANCE_YEAR};
+use zero_raf_host::salts::{random_salt, write_salts, MemberSalt};
+use zero_raf_methods::{ZERO_RAF_RADV_ELF, ZERO_RAF_RADV_ID};
+use risc0_zkvm::serde::{from_slice, to_vec};
+use risc0_zkvm::{Executor, ExecutorEnv};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/*
+    Proves for RADV whether a named HCC is present for a member, after hierarchies, and which
+    dx_to_cc row supports it. The member is identified by the salted commitment of their
+    private input; other diagnoses are not disclosed. The salt comes from the OS CSPRNG and is
+    written to a separate, owner-only file that must stay with the plan. Until patient files
+    are read the member is synthetic.
+
+    Usage: radv --hcc HCC37 [--seed 42] [--execute] [--out zero-raf-radv.json]
+                [--salts zero-raf-radv-salts.json]
+*/
+struct RadvArgs {
+    hcc: String,
+    seed: u64,
+    execute_only: bool,
+    out: String,
+    salts: String,
+}
+
+fn parse_args() -> Result<RadvArgs, Box<dyn Error>> {
+
+    let mut hcc = None;
+    let mut args = RadvArgs {
+        hcc: String::new(),
+        seed: 42,
+        execute_only: false,
+        out: "zero-raf-radv.json".to_string(),
+        salts: "zero-raf-radv-salts.json".to_string(),
+    };
+
+    let mut argv = std::env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        let mut value = || argv.next().ok_or(format!("{} expects a value", arg));
+        match arg.as_str() {
+            "--hcc" => hcc = Some(value()?.to_uppercase()),
+            "--seed" => args.seed = value()?.parse()?,
+            "--execute" => args.execute_only = true,
+            "--out" => args.out = value()?,
+            "--salts" => args.salts = value()?,
+            _ => return Err(format!("unknown argument {}", arg).into()),
+        }
+    }
+    args.hcc = hcc.ok_or("expected --hcc <HCC>, e.g. --hcc HCC37")?;
+    Ok(args)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+
+    let args = parse_args()?;
+
+    let public_inputs = load_public_inputs(&get_cms_data_dir(PERFORMANCE_YEAR))?;
+    let table_words = CompiledRAFTables::compile(&public_inputs)?.to_words();
+    let dx_table = DxMerkleTable::from_dx_to_cc::<Sha2Hasher>(&public_inputs.dx_to_cc);
+    let private_input = SyntheticPopulation::new(&public_inputs.dx_to_cc, PopulationProfile::default(), args.seed)?.next_beneficiary();
+
+    // The plan keeps the salt with its submission and publishes only the commitment. The
+    // salt is secret, so it comes from the OS CSPRNG and not from the public seed.
+    let salt = random_salt()?;
+    let commitment = input_commitment::<Sha2Hasher>(&private_input, &salt);
+    write_salts(Path::new(&args.salts), &[MemberSalt { input_commitment: commitment, salt }])?;
+    println!("Input commitment: {}, private salt written to {}", hash_to_hex(&commitment), args.salts);
+
+    let env = ExecutorEnv::builder()
+                .add_input(&to_vec(&table_words).unwrap())
+                .add_input(&to_vec(&dx_table.commitment()).unwrap())
+                .add_input(&to_vec(&public_inputs.interactions).unwrap())
+                .add_input(&to_vec(&commitment).unwrap())
+                .add_input(&to_vec(&args.hcc).unwrap())
+                .add_input(&to_vec(&salt).unwrap())
+                .add_input(&to_vec(&private_input).unwrap())
+                .add_input(&to_vec(&dx_table.lookups(&normalize_diagnosis_codes(&private_input.diagnosis_codes))).unwrap())
+                .build();
+    let mut exec = Executor::from_elf(env, ZERO_RAF_RADV_ELF)?;
+    let session = exec.run()?;
+
+    let journal: HccPresenceJournal = if args.execute_only {
+        println!("UNPROVEN: --execute skips proving, this answer is not backed by a receipt");
+        from_slice(&session.journal)?
+    } else {
+        let receipt = session.prove()?;
+        receipt.verify(ZERO_RAF_RADV_ID)?;
+        fs::write("zero-raf-radv-receipt.txt", serde_json::to_string(&receipt)?)?;
+        println!("Proved and verified the RADV receipt");
+        from_slice(&receipt.journal)?
+    };
+
+    match &journal.supporting_row {
+        Some(row) => println!("{} present, supported by {} -> {}", journal.hcc, row.dx, row.hccs.join(",")),
+        None => println!("{} not present", journal.hcc),
+    }
+    fs::write(&args.out, serde_json::to_string_pretty(&journal)?)?;
+    println!("Wrote HCC presence result to {}", args.out);
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use zero_raf_core::compiled::CompiledRAFTables;
+use zero_raf_core::dx_table::DxMerkleTable;
+use zero_raf_core::merkle::Sha2Hasher;
+use zero_raf_core::synthetic::{PopulationProfile, SyntheticPopulation};
+use zero_raf_core::utils::{get_cms_data_dir, load_public_inputs, PERFORMANCE_YEAR};
+use zero_raf_core::jobs::{JobInputs, JobManifest};
+use zero_raf_core::{Journal, PrivateRAFInput};
+use zero_raf_host::jobs::{run_job, JobDir};
+use zero_raf_host::proving::{prove_roster, ProvingError, ProvingOptions, ScoringTables};
+use zero_raf_methods::ZERO_RAF_ID;
+use risc0_zkvm::SessionReceipt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/*
+    Proves a whole roster with the scoring Guest, one receipt per member, on a pool of
+    workers. The roster is split into chunks of --chunk-size members and --workers chunks
+    are proven at the same time; --segment-po2 caps the segment size (and the memory each
+    worker needs). --workers defaults to 1: each worker proves a whole segment in memory, so
+    raise it only as far as the memory of the machine allows, not to the core count. Until
+    roster files are read the roster is synthetic.
+
+    Usage: roster [resume] [--members 100] [--seed 42] [--workers 1] [--chunk-size 10]
+                  [--segment-po2 <po2>] [--execute] [--job <dir>] [--out zero-raf-roster.json]
+
+    The journals are written to --out and the receipts to zero-raf-roster-receipts.json.
+    With --job every chunk is checkpointed to a job directory as it is proven, see
+    `zero_raf_host::jobs`, and `roster resume --job <dir>` with the same roster and tables
+    proves only the chunks that are missing.
+*/
+struct RosterArgs {
+    resume: bool,
+    members: usize,
+    seed: u64,
+    options: ProvingOptions,
+    job: Option<PathBuf>,
+    out: String,
+}
+
+fn parse_args() -> Result<RosterArgs, ProvingError> {
+
+    let mut args = RosterArgs {
+        resume: false,
+        members: 100,
+        seed: 42,
+        options: ProvingOptions::default(),
+        job: None,
+        out: "zero-raf-roster.json".to_string(),
+    };
+
+    let mut argv = std::env::args().skip(1).peekable();
+    if argv.peek().map(|x| x.as_str()) == Some("resume") {
+        args.resume = true;
+        argv.next();
+    }
+    while let Some(arg) = argv.next() {
+        let mut value = || argv.next().ok_or(format!("{} expects a value", arg));
+        match arg.as_str() {
+            "--members" => args.members = value()?.parse()?,
+            "--seed" => args.seed = value()?.parse()?,
+            "--workers" => args.options.workers = value()?.parse()?,
+            "--chunk-size" => args.options.chunk_size = value()?.parse()?,
+            "--segment-po2" => args.options.segment_limit_po2 = Some(value()?.parse()?),
+            "--execute" => args.options.execute_only = true,
+            "--job" => args.job = Some(PathBuf::from(value()?)),
+            "--out" => args.out = value()?,
+            _ => return Err(format!("unknown argument {}", arg).into()),
+        }
+    }
+    if args.options.workers == 0 || args.options.chunk_size == 0 {
+        return Err("--workers and --chunk-size must be at least 1".into());
+    }
+    if args.resume && args.job.is_none() {
+        return Err("resume expects --job <dir>".into());
+    }
+    if args.job.is_some() && args.options.execute_only {
+        return Err("--job checkpoints receipts and cannot be combined with --execute".into());
+    }
+    Ok(args)
+}
+
+fn main() -> Result<(), ProvingError> {
+
+    let args = parse_args()?;
+
+    let public_inputs = load_public_inputs(&get_cms_data_dir(PERFORMANCE_YEAR))?;
+    let table_words = CompiledRAFTables::compile(&public_inputs)?.to_words();
+    let dx_table = DxMerkleTable::from_dx_to_cc::<Sha2Hasher>(&public_inputs.dx_to_cc);
+    let roster = SyntheticPopulation::new(&public_inputs.dx_to_cc, PopulationProfile::default(), args.seed)?.roster(args.members);
+    let tables = ScoringTables { words: &table_words, dx_table: &dx_table, interactions: &public_inputs.interactions };
+
+    if let Some(path) = &args.job {
+        return prove_job(&args, path, tables, &roster);
+    }
+
+    println!("{} {} members in chunks of {} on {} workers",
+             if args.options.execute_only { "Executing" } else { "Proving" },
+             roster.len(), args.options.chunk_size, args.options.workers);
+    let started = Instant::now();
+    let results = prove_roster(tables, &roster, &args.options, |progress| println!("{}", progress))?;
+    println!("Done in {:.1}s", started.elapsed().as_secs_f64());
+
+    if args.options.execute_only {
+        println!("UNPROVEN: --execute skips proving, these journals are not backed by receipts");
+    }
+    let (journals, receipts): (Vec<Journal>, Vec<Option<SessionReceipt>>) = results.into_iter().map(|x| (x.journal, x.receipt)).unzip();
+    fs::write(&args.out, serde_json::to_string_pretty(&journals)?)?;
+    println!("Wrote {} journals to {}", journals.len(), args.out);
+    if !args.options.execute_only {
+        fs::write("zero-raf-roster-receipts.json", serde_json::to_string(&receipts)?)?;
+        println!("Wrote {} verified receipts to zero-raf-roster-receipts.json", receipts.len());
+    }
+
+    Ok(())
+}
+
+// Starts or resumes a checkpointed job, the receipts stay in the job directory
+fn prove_job(args: &RosterArgs, path: &Path, tables: ScoringTables, roster: &[PrivateRAFInput]) -> Result<(), ProvingError> {
+
+    let inputs = JobInputs::new::<Sha2Hasher>(ZERO_RAF_ID, tables.words, &tables.dx_table.commitment(), tables.interactions, roster);
+    let job = if args.resume {
+        JobDir::open(path, &inputs)?
+    } else {
+        JobDir::create(path, JobManifest::new(inputs, args.options.chunk_size))?
+    };
+    let pending = job.manifest.pending().len();
+    println!("Job {}: {} of {} chunks to prove on {} workers", path.display(), pending, job.manifest.chunks.len(), args.options.workers);
+
+    let started = Instant::now();
+    let job = run_job(job, tables, roster, &args.options, |progress| println!("{}", progress))?;
+    println!("Done in {:.1}s", started.elapsed().as_secs_f64());
+
+    let journals = job.journals()?;
+    fs::write(&args.out, serde_json::to_string_pretty(&journals)?)?;
+    println!("Wrote {} journals to {}", journals.len(), args.out);
+    Ok(())
+}
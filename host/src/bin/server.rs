@@ -0,0 +1,71 @@
+use zero_raf_core::compiled::CompiledRAFTables;
+use zero_raf_core::dx_table::DxMerkleTable;
+use zero_raf_core::merkle::Sha2Hasher;
+use zero_raf_core::utils::{get_cms_data_dir, load_public_inputs, PERFORMANCE_YEAR};
+use zero_raf_host::proving::{ProvingError, ProvingOptions, ScoringTables};
+use zero_raf_host::service::{run_jobs, serve, DirectoryJobStore, JobStore, MemoryJobStore, MAX_QUEUED_JOBS};
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc};
+
+/*
+    Local proving service: payer operations tools submit rosters over HTTP, poll the job
+    status and download journals and receipts, see `zero_raf_host::service` for the
+    endpoints. Jobs are kept in memory unless --store names a directory.
+
+    Usage: server [--addr 127.0.0.1:8080] [--store <dir>] [--workers 1] [--chunk-size 10]
+                  [--segment-po2 <po2>]
+*/
+struct ServerArgs {
+    addr: String,
+    store: Option<PathBuf>,
+    options: ProvingOptions,
+}
+
+fn parse_args() -> Result<ServerArgs, ProvingError> {
+
+    let mut args = ServerArgs { addr: "127.0.0.1:8080".to_string(), store: None, options: ProvingOptions::default() };
+
+    let mut argv = std::env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        let mut value = || argv.next().ok_or(format!("{} expects a value", arg));
+        match arg.as_str() {
+            "--addr" => args.addr = value()?,
+            "--store" => args.store = Some(PathBuf::from(value()?)),
+            "--workers" => args.options.workers = value()?.parse()?,
+            "--chunk-size" => args.options.chunk_size = value()?.parse()?,
+            "--segment-po2" => args.options.segment_limit_po2 = Some(value()?.parse()?),
+            _ => return Err(format!("unknown argument {}", arg).into()),
+        }
+    }
+    if args.options.workers == 0 || args.options.chunk_size == 0 {
+        return Err("--workers and --chunk-size must be at least 1".into());
+    }
+    Ok(args)
+}
+
+fn main() -> Result<(), ProvingError> {
+
+    let args = parse_args()?;
+
+    // Loaded once, every job is scored with the same tables
+    let public_inputs = load_public_inputs(&get_cms_data_dir(PERFORMANCE_YEAR))?;
+    let table_words = CompiledRAFTables::compile(&public_inputs)?.to_words();
+    let dx_table = DxMerkleTable::from_dx_to_cc::<Sha2Hasher>(&public_inputs.dx_to_cc);
+
+    let store: Arc<dyn JobStore> = match &args.store {
+        Some(path) => Arc::new(DirectoryJobStore::open(path.clone())?),
+        None => Arc::new(MemoryJobStore::default()),
+    };
+
+    // Bounded, so rosters cannot pile up in memory faster than they are proven
+    let (queue, jobs) = mpsc::sync_channel(MAX_QUEUED_JOBS);
+    let worker_store = store.clone();
+    let options = args.options.clone();
+    std::thread::spawn(move || {
+        let tables = ScoringTables { words: &table_words, dx_table: &dx_table, interactions: &public_inputs.interactions };
+        run_jobs(worker_store, jobs, tables, &options);
+    });
+
+    println!("Listening on http://{} with {} workers per job", args.addr, args.options.workers);
+    serve(&args.addr, store, queue)
+}
@@ -0,0 +1,116 @@
+use zero_raf_core::compiled::CompiledRAFTables;
+use zero_raf_core::diagnosis::normalize_diagnosis_codes;
+use zero_raf_core::dx_table::DxMerkleTable;
+use zero_raf_core::merkle::{hash_to_hex, Sha2Hasher};
+use zero_raf_core::radv::input_commitment;
+use zero_raf_core::synthetic::{PopulationProfile, SyntheticPopulation};
+use zero_raf_core::threshold::{ScoreCondition, ThresholdJournal};
+use zero_raf_core::utils::{get_cms_data_dir, load_public_inputs, PERFORMANCE_YEAR};
+use zero_raf_host::salts::{random_salt, write_salts, MemberSalt};
+use zero_raf_methods::{ZERO_RAF_THRESHOLD_ELF, ZERO_RAF_THRESHOLD_ID};
+use risc0_zkvm::serde::{from_slice, to_vec};
+use risc0_zkvm::{Executor, ExecutorEnv};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/*
+    Proves whether the risk score of a beneficiary meets a program cutoff or falls in a
+    range, committing only the answer, the segment used, the salted commitment of the
+    member's input and the public tables digest. The salt comes from the OS CSPRNG and is
+    written to a separate, owner-only file that must stay with the plan. Until patient files
+    are read the beneficiary is synthetic.
+
+    Usage: threshold (--at-least 1.5 | --between 1.0,2.0) [--seed 42] [--execute]
+                     [--out zero-raf-threshold.json] [--salts zero-raf-threshold-salts.json]
+*/
+struct ThresholdArgs {
+    condition: ScoreCondition,
+    seed: u64,
+    execute_only: bool,
+    out: String,
+    salts: String,
+}
+
+fn parse_args() -> Result<ThresholdArgs, Box<dyn Error>> {
+
+    let mut condition = None;
+    let mut args = ThresholdArgs {
+        condition: ScoreCondition::AtLeast(0.0),
+        seed: 42,
+        execute_only: false,
+        out: "zero-raf-threshold.json".to_string(),
+        salts: "zero-raf-threshold-salts.json".to_string(),
+    };
+
+    let mut argv = std::env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        let mut value = || argv.next().ok_or(format!("{} expects a value", arg));
+        match arg.as_str() {
+            "--at-least" => condition = Some(ScoreCondition::AtLeast(value()?.parse()?)),
+            "--between" => {
+                let range = value()?;
+                let (low, high) = range.split_once(',').ok_or(format!("--between expects low,high, found {}", range))?;
+                condition = Some(ScoreCondition::Between { low: low.trim().parse()?, high: high.trim().parse()? });
+            }
+            "--seed" => args.seed = value()?.parse()?,
+            "--execute" => args.execute_only = true,
+            "--out" => args.out = value()?,
+            "--salts" => args.salts = value()?,
+            _ => return Err(format!("unknown argument {}", arg).into()),
+        }
+    }
+    args.condition = condition.ok_or("expected --at-least <cutoff> or --between <low>,<high>")?;
+    if !args.condition.is_valid() {
+        return Err(format!("condition {} cannot be checked", args.condition).into());
+    }
+    Ok(args)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+
+    let args = parse_args()?;
+
+    let public_inputs = load_public_inputs(&get_cms_data_dir(PERFORMANCE_YEAR))?;
+    let table_words = CompiledRAFTables::compile(&public_inputs)?.to_words();
+    let dx_table = DxMerkleTable::from_dx_to_cc::<Sha2Hasher>(&public_inputs.dx_to_cc);
+    let private_input = SyntheticPopulation::new(&public_inputs.dx_to_cc, PopulationProfile::default(), args.seed)?.next_beneficiary();
+
+    // The plan keeps the salt with its submission and publishes only the commitment. The
+    // salt is secret, so it comes from the OS CSPRNG and not from the public seed.
+    let salt = random_salt()?;
+    let commitment = input_commitment::<Sha2Hasher>(&private_input, &salt);
+    write_salts(Path::new(&args.salts), &[MemberSalt { input_commitment: commitment, salt }])?;
+    println!("Input commitment: {}, private salt written to {}", hash_to_hex(&commitment), args.salts);
+
+    let env = ExecutorEnv::builder()
+                .add_input(&to_vec(&table_words).unwrap())
+                .add_input(&to_vec(&dx_table.commitment()).unwrap())
+                .add_input(&to_vec(&public_inputs.interactions).unwrap())
+                .add_input(&to_vec(&args.condition).unwrap())
+                .add_input(&to_vec(&commitment).unwrap())
+                .add_input(&to_vec(&salt).unwrap())
+                .add_input(&to_vec(&private_input).unwrap())
+                .add_input(&to_vec(&dx_table.lookups(&normalize_diagnosis_codes(&private_input.diagnosis_codes))).unwrap())
+                .build();
+    let mut exec = Executor::from_elf(env, ZERO_RAF_THRESHOLD_ELF)?;
+    let session = exec.run()?;
+
+    let journal: ThresholdJournal = if args.execute_only {
+        println!("UNPROVEN: --execute skips proving, this answer is not backed by a receipt");
+        from_slice(&session.journal)?
+    } else {
+        let receipt = session.prove()?;
+        receipt.verify(ZERO_RAF_THRESHOLD_ID)?;
+        fs::write("zero-raf-threshold-receipt.txt", serde_json::to_string(&receipt)?)?;
+        println!("Proved and verified the threshold receipt");
+        from_slice(&receipt.journal)?
+    };
+
+    println!("{} on {} ({}): {}", journal.condition, journal.score, journal.segment, if journal.met { "met" } else { "not met" });
+    println!("Tables digest: {}", hash_to_hex(&journal.tables_digest));
+    fs::write(&args.out, serde_json::to_string_pretty(&journal)?)?;
+    println!("Wrote threshold result to {}", args.out);
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use zero_raf_core::compiled::{public_inputs_digest, tables_digest, CompiledRAFTables};
+use zero_raf_core::dx_table::DxMerkleTable;
+use zero_raf_core::manifest::TableManifest;
+use zero_raf_core::merkle::Sha2Hasher;
+use zero_raf_core::utils::{get_cms_data_dir, load_public_inputs, MODEL_VERSION, PERFORMANCE_YEAR, PUBLIC_INPUT_FILES};
+use zero_raf_host::proving::ProvingError;
+use zero_raf_host::bundle::BUNDLE_MAGIC;
+use zero_raf_host::verify::{read_receipt, verify_bundle, verify_receipt, PolicyEntry, VerificationPolicy, Verdict, MAX_RECEIPT_BYTES};
+use zero_raf_methods::ZERO_RAF_ID;
+use std::fs;
+use tiny_http::{Header, Response, Server};
+
+/*
+    Verification-only service for external auditors. POST /verify with a receipt bundle
+    (see `zero_raf_host::bundle`) or a serialized SessionReceipt as the body answers with a
+    `Verdict` as JSON: whether the receipt verified against the allowed image IDs and policy
+    entries (CMS release and public inputs digest) of --policy, why, and the decoded
+    Journal. Bodies over `MAX_RECEIPT_BYTES` are refused with 413. --receipt verifies a
+    single file and exits instead.
+
+    Usage: verifier --policy policy.json [--addr 127.0.0.1:8081] [--receipt zero-raf-receipt.bundle]
+           verifier --print-policy
+
+    --print-policy writes the policy of this build (its scoring image ID and one entry with its
+    performance year, model version, and the table manifest digest and public inputs digest
+    of the CMS tables on disk) for the plan to hand to its auditors.
+*/
+struct VerifierArgs {
+    policy: Option<String>,
+    addr: String,
+    receipt: Option<String>,
+    print_policy: bool,
+}
+
+fn parse_args() -> Result<VerifierArgs, ProvingError> {
+
+    let mut args = VerifierArgs { policy: None, addr: "127.0.0.1:8081".to_string(), receipt: None, print_policy: false };
+
+    let mut argv = std::env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        let mut value = || argv.next().ok_or(format!("{} expects a value", arg));
+        match arg.as_str() {
+            "--policy" => args.policy = Some(value()?),
+            "--addr" => args.addr = value()?,
+            "--receipt" => args.receipt = Some(value()?),
+            "--print-policy" => args.print_policy = true,
+            _ => return Err(format!("unknown argument {}", arg).into()),
+        }
+    }
+    if args.policy.is_none() && !args.print_policy {
+        return Err("expected --policy <file> or --print-policy".into());
+    }
+    Ok(args)
+}
+
+fn main() -> Result<(), ProvingError> {
+
+    let args = parse_args()?;
+
+    if args.print_policy {
+        let cms_dir = get_cms_data_dir(PERFORMANCE_YEAR);
+        let public_inputs = load_public_inputs(&cms_dir)?;
+        let words = CompiledRAFTables::compile(&public_inputs)?.to_words();
+        let dx_table = DxMerkleTable::from_dx_to_cc::<Sha2Hasher>(&public_inputs.dx_to_cc).commitment();
+        let digest = public_inputs_digest::<Sha2Hasher>(&tables_digest::<Sha2Hasher>(&words), &dx_table, &public_inputs.interactions);
+        let policy = VerificationPolicy {
+            image_ids: vec![ZERO_RAF_ID],
+            entries: vec![PolicyEntry {
+                performance_year: PERFORMANCE_YEAR.to_string(),
+                model_version: MODEL_VERSION.to_string(),
+                table_manifest_digest: TableManifest::read::<Sha2Hasher>(&cms_dir, &PUBLIC_INPUT_FILES)?.digest::<Sha2Hasher>(),
+                public_inputs_digest: digest,
+            }],
+        };
+        println!("{}", policy.to_json()?);
+        return Ok(());
+    }
+
+    let policy = VerificationPolicy::from_json(&fs::read_to_string(args.policy.as_ref().unwrap())?)?;
+
+    if let Some(path) = &args.receipt {
+        let verdict = verify(&fs::read(path)?, &policy);
+        println!("{}", serde_json::to_string_pretty(&verdict)?);
+        if !verdict.verified {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let server = Server::http(&args.addr)?;
+    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+    println!("Verifying receipts on http://{}/verify for {} image IDs and {} policy entries",
+             args.addr, policy.image_ids.len(), policy.entries.len());
+    for mut request in server.incoming_requests() {
+        let (code, body) = if request.method().as_str() == "POST" && request.url() == "/verify" {
+            let body_length = request.body_length();
+            match read_receipt(request.as_reader(), body_length) {
+                Ok(Some(receipt)) => (200, serde_json::to_string(&verify(&receipt, &policy))?),
+                Ok(None) => (413, serde_json::json!({ "error": format!("the body is larger than {} bytes", MAX_RECEIPT_BYTES) }).to_string()),
+                Err(e) => (400, serde_json::json!({ "error": e.to_string() }).to_string()),
+            }
+        } else {
+            (404, serde_json::json!({ "error": "not found, POST a receipt to /verify" }).to_string())
+        };
+        let response = Response::from_string(body).with_status_code(code).with_header(content_type.clone());
+        if let Err(e) = request.respond(response) {
+            eprintln!("Unable to answer a request: {}", e);
+        }
+    }
+    Ok(())
+}
+
+// Bundles are told apart from receipts serialized as JSON by their magic
+fn verify(bytes: &[u8], policy: &VerificationPolicy) -> Verdict {
+    if bytes.starts_with(&BUNDLE_MAGIC) {
+        return verify_bundle(bytes, policy);
+    }
+    verify_receipt(&String::from_utf8_lossy(bytes), policy)
+}
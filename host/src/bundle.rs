@@ -0,0 +1,132 @@
+use zero_raf_core::manifest::TableManifest;
+use zero_raf_core::merkle::{hash_to_hex, Hash, Sha2Hasher};
+use zero_raf_core::utils::{get_cms_data_dir, MODEL_VERSION, PUBLIC_INPUT_FILES};
+use zero_raf_core::Journal;
+use crate::proving::ProvingError;
+use crate::verify::image_id_to_hex;
+use risc0_zkvm::serde::from_slice;
+use risc0_zkvm::SessionReceipt;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Self-describing receipt bundles.
+//
+// A bundle keeps a scoring receipt together with what is needed to make sense of it: the
+// image ID it was proven for, the decoded journal, the performance year and model version,
+// the digest of the compiled tables, the manifest of the CMS files they were compiled from,
+// when it was created and by which version of this tool.
+//
+// Binary encoding: the magic "ZRAF", the format version (u32, little endian), then the
+// bincode encoding of `ReceiptBundle`. The version is read before anything else is decoded,
+// so a bundle of another version is refused with a clear error. `to_json` gives a read-only
+// view for people and tools, with IDs and digests as hex.
+
+pub const BUNDLE_MAGIC: [u8; 4] = *b"ZRAF";
+
+// Version of the bundle layout
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+pub struct ReceiptBundle {
+    // e.g. PY2023
+    pub performance_year: String,
+
+    // See `utils::MODEL_VERSION`
+    pub model_version: String,
+
+    pub image_id: [u32; 8],
+
+    // Compiled tables the journal was computed with, see `compiled::tables_digest`
+    pub tables_digest: Hash,
+
+    // CMS files the tables were compiled from
+    pub table_manifest: TableManifest,
+
+    // Seconds since the UNIX epoch
+    pub created_unix: u64,
+
+    // Version of zero-raf-host that wrote the bundle
+    pub tool_version: String,
+
+    pub journal: Journal,
+    pub receipt: SessionReceipt,
+}
+
+// JSON view of a bundle
+#[derive(Serialize)]
+struct BundleView<'a> {
+    format_version: u32,
+    performance_year: &'a str,
+    model_version: &'a str,
+    image_id: String,
+    tables_digest: String,
+    table_manifest_digest: String,
+    table_manifest: Vec<(&'a str, String)>,
+    created_unix: u64,
+    tool_version: &'a str,
+    journal: &'a Journal,
+    receipt: &'a SessionReceipt,
+}
+
+impl ReceiptBundle {
+
+    /// Bundles a scoring receipt with the metadata of the CMS tables of `performance_year`
+    pub fn new(receipt: SessionReceipt, image_id: [u32; 8], performance_year: &str) -> Result<Self, ProvingError> {
+        let journal: Journal = from_slice(&receipt.journal)?;
+        Ok(ReceiptBundle {
+            performance_year: performance_year.to_string(),
+            model_version: MODEL_VERSION.to_string(),
+            image_id,
+            tables_digest: journal.tables_digest,
+            table_manifest: TableManifest::read::<Sha2Hasher>(&get_cms_data_dir(performance_year), &PUBLIC_INPUT_FILES)?,
+            created_unix: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            journal,
+            receipt,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ProvingError> {
+        let mut bytes = BUNDLE_MAGIC.to_vec();
+        bytes.extend_from_slice(&BUNDLE_FORMAT_VERSION.to_le_bytes());
+        bytes.extend(bincode::serialize(self)?);
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProvingError> {
+        if bytes.len() < 8 || bytes[..4] != BUNDLE_MAGIC {
+            return Err("not a receipt bundle".into());
+        }
+        let version = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        if version != BUNDLE_FORMAT_VERSION {
+            return Err(format!("bundle format version {} is not supported, expected {}", version, BUNDLE_FORMAT_VERSION).into());
+        }
+        Ok(bincode::deserialize(&bytes[8..])?)
+    }
+
+    pub fn to_json(&self) -> Result<String, ProvingError> {
+        let view = BundleView {
+            format_version: BUNDLE_FORMAT_VERSION,
+            performance_year: &self.performance_year,
+            model_version: &self.model_version,
+            image_id: image_id_to_hex(&self.image_id),
+            tables_digest: hash_to_hex(&self.tables_digest),
+            table_manifest_digest: hash_to_hex(&self.table_manifest.digest::<Sha2Hasher>()),
+            table_manifest: self.table_manifest.files.iter().map(|x| (x.name.as_str(), hash_to_hex(&x.sha256))).collect(),
+            created_unix: self.created_unix,
+            tool_version: &self.tool_version,
+            journal: &self.journal,
+            receipt: &self.receipt,
+        };
+        Ok(serde_json::to_string_pretty(&view)?)
+    }
+}
+
+#[test]
+fn refuses_other_bundle_versions() {
+    let mut bytes = BUNDLE_MAGIC.to_vec();
+    bytes.extend_from_slice(&(BUNDLE_FORMAT_VERSION + 1).to_le_bytes());
+    let error = ReceiptBundle::from_bytes(&bytes).err().unwrap().to_string();
+    assert!(error.starts_with("bundle format version 2 is not supported"));
+    assert_eq!(ReceiptBundle::from_bytes(b"{\"segments\": []}").err().unwrap().to_string(), "not a receipt bundle");
+}
@@ -0,0 +1,146 @@
+use zero_raf_core::chunks::{run_chunks, ChunkProgress};
+use zero_raf_core::jobs::{JobInputs, JobManifest};
+use zero_raf_core::{Journal, PrivateRAFInput};
+use crate::proving::{prove_member, MemberResult, ProvingError, ProvingOptions, ScoringTables};
+use risc0_zkvm::serde::from_slice;
+use risc0_zkvm::SessionReceipt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// On-disk proving jobs that survive a crash.
+//
+// Layout of a job directory:
+//     job.json                          `JobManifest`: input digests and the proven chunks
+//     chunks/chunk-00003.journals.json  journals of the members of chunk 3, in roster order
+//     chunks/chunk-00003.receipts.json  their receipts, verified against the image ID
+//
+// The chunk files are written and synced to disk, with the chunks directory, before the chunk
+// is marked proven, and job.json is replaced atomically, so a job can always be resumed from
+// what job.json says. When the journals are read back, every receipt is verified again and
+// its journal compared to the stored one, so a chunk file edited or damaged on disk is caught.
+
+pub const MANIFEST_FILE: &str = "job.json";
+
+pub struct JobDir {
+    path: PathBuf,
+    pub manifest: JobManifest,
+}
+
+impl JobDir {
+
+    /// Starts a new job in `path`, refusing to overwrite an existing job
+    pub fn create(path: &Path, manifest: JobManifest) -> Result<Self, ProvingError> {
+        if path.join(MANIFEST_FILE).exists() {
+            return Err(format!("{} already holds a job, resume it instead", path.display()).into());
+        }
+        fs::create_dir_all(path.join("chunks"))?;
+        let job = JobDir { path: path.to_path_buf(), manifest };
+        job.save()?;
+        Ok(job)
+    }
+
+    /// Reads the manifest of an existing job, checking it can be resumed with `inputs`
+    pub fn open(path: &Path, inputs: &JobInputs) -> Result<Self, ProvingError> {
+        let manifest: JobManifest = serde_json::from_str(&fs::read_to_string(path.join(MANIFEST_FILE))?)?;
+        manifest.check_resume(inputs).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(JobDir { path: path.to_path_buf(), manifest })
+    }
+
+    // Replaces job.json through a temporary file so a crash never leaves half a manifest
+    fn save(&self) -> Result<(), ProvingError> {
+        let temporary = self.path.join(format!("{}.tmp", MANIFEST_FILE));
+        write_synced(&temporary, serde_json::to_string_pretty(&self.manifest)?.as_bytes())?;
+        fs::rename(&temporary, self.path.join(MANIFEST_FILE))?;
+        sync_dir(&self.path)?;
+        Ok(())
+    }
+
+    fn chunk_path(&self, chunk: usize, kind: &str) -> PathBuf {
+        self.path.join("chunks").join(format!("chunk-{:05}.{}.json", chunk, kind))
+    }
+
+    /// Stores the results of a chunk and marks it proven
+    pub fn checkpoint(&mut self, chunk: usize, results: &[MemberResult]) -> Result<(), ProvingError> {
+        let journals: Vec<&Journal> = results.iter().map(|x| &x.journal).collect();
+        let receipts: Vec<&SessionReceipt> = results
+            .iter()
+            .map(|x| x.receipt.as_ref().ok_or("only proven members can be checkpointed"))
+            .collect::<Result<_, _>>()?;
+        write_synced(&self.chunk_path(chunk, "journals"), serde_json::to_string(&journals)?.as_bytes())?;
+        write_synced(&self.chunk_path(chunk, "receipts"), serde_json::to_string(&receipts)?.as_bytes())?;
+
+        // The chunk files must be on disk before job.json says they are
+        sync_dir(&self.path.join("chunks"))?;
+        self.manifest.mark_proven(chunk).map_err(|e| e.to_string())?;
+        self.save()
+    }
+
+    /*
+        Journals of the whole roster, once every chunk is proven. Each receipt is verified
+        against the image ID of the job and must commit exactly the stored journal.
+    */
+    pub fn journals(&self) -> Result<Vec<Journal>, ProvingError> {
+        if !self.manifest.is_complete() {
+            return Err(format!("{} chunks are not proven yet", self.manifest.pending().len()).into());
+        }
+        let mut journals = vec![];
+        for (chunk, status) in self.manifest.chunks.iter().enumerate() {
+            let chunk_journals: Vec<Journal> = serde_json::from_str(&fs::read_to_string(self.chunk_path(chunk, "journals"))?)?;
+            let receipts: Vec<SessionReceipt> = serde_json::from_str(&fs::read_to_string(self.chunk_path(chunk, "receipts"))?)?;
+            if chunk_journals.len() != status.members().len() || receipts.len() != status.members().len() {
+                return Err(format!("chunk {}: expected {} journals and receipts, found {} and {}",
+                                   chunk, status.members().len(), chunk_journals.len(), receipts.len()).into());
+            }
+            for (member, (journal, receipt)) in status.members().zip(chunk_journals.iter().zip(&receipts)) {
+                receipt.verify(self.manifest.inputs.image_id).map_err(|e| format!("member {}: receipt does not verify: {}", member, e))?;
+                let committed: Journal = from_slice(&receipt.journal)?;
+                if committed != *journal {
+                    return Err(format!("member {}: stored journal differs from the one the receipt commits", member).into());
+                }
+            }
+            journals.extend(chunk_journals);
+        }
+        Ok(journals)
+    }
+}
+
+// Writes the file and waits until its contents are on disk
+fn write_synced(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+// Syncs a directory so the files created or renamed in it survive a crash
+fn sync_dir(path: &Path) -> io::Result<()> {
+    // Directories cannot be opened as files on every platform, Windows flushes them on its own
+    if cfg!(unix) {
+        File::open(path)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/*
+    Proves the chunks of the job that are not proven yet, checkpointing each chunk as it
+    completes. Progress counts only the chunks proven by this run.
+*/
+pub fn run_job<P>(job: JobDir, tables: ScoringTables, roster: &[PrivateRAFInput], options: &ProvingOptions, progress: P) -> Result<JobDir, ProvingError>
+where
+    P: Fn(&ChunkProgress) + Sync,
+{
+    if options.execute_only {
+        return Err("a job stores receipts, it cannot be run with execute_only".into());
+    }
+    let pending = job.manifest.pending();
+    let ranges: Vec<_> = pending.iter().map(|x| job.manifest.chunks[*x].members()).collect();
+    let job = Mutex::new(job);
+    run_chunks(&ranges, options.workers, |index, members| {
+        let results = members
+            .map(|member| prove_member(tables, member, &roster[member], options))
+            .collect::<Result<Vec<MemberResult>, ProvingError>>()?;
+        job.lock().unwrap().checkpoint(pending[index], &results)
+    }, progress)?;
+    Ok(job.into_inner().unwrap())
+}
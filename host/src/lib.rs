@@ -0,0 +1,8 @@
+// Host side proving shared by the binaries of this crate.
+
+pub mod bundle;
+pub mod jobs;
+pub mod proving;
+pub mod salts;
+pub mod service;
+pub mod verify;
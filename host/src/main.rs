@@ -1,10 +1,18 @@
-use zero_raf_core::{PublicRAFInputs, PrivateRAFInput};
-use zero_raf_methods::{ZERO_RAF_ELF, ZERO_RAF_ID};
-use zero_raf_core::utils::{get_cms_data_dir, read_hcc_coefficients, read_hier, read_dx_to_cc, read_hcc_labels};
-use risc0_zkvm::serde::{to_vec};
-use risc0_zkvm::{Executor, ExecutorEnv, Session, Segment, SessionReceipt};
+use zero_raf_core::{Journal, PrivateRAFInput};
+use zero_raf_methods::ZERO_RAF_ID;
+use zero_raf_core::compiled::{CompiledRAFTables, CompiledTablesView};
+use zero_raf_core::diagnosis::validate_diagnosis_codes;
+use zero_raf_core::dx_table::DxMerkleTable;
+use zero_raf_core::interactions::InteractionDefinitions;
+use zero_raf_core::merkle::{hash_to_hex, Sha2Hasher};
+use zero_raf_core::preview::{score_natively, ScoringMode, UnprovenJournal};
+use zero_raf_core::synthetic::{PopulationProfile, SyntheticPopulation};
+use zero_raf_core::utils::{get_cms_data_dir, load_public_inputs, PERFORMANCE_YEAR};
+use zero_raf_host::bundle::ReceiptBundle;
+use zero_raf_host::proving::{execute_member, ScoringTables};
+use risc0_zkvm::serde::from_slice;
+use risc0_zkvm::{Session, Segment, SessionReceipt};
 use std::error::Error;
-use std::collections::BTreeMap;
 use std::{fs, path::PathBuf};
 use serde_json;
 
@@ -29,48 +37,64 @@ fn main() -> Result<(), Box<dyn Error>> {
         Phase 2: Read in the demographic data for 1 or more patients to pass to the Guest code
     */
 
-    let cms_dir = get_cms_data_dir("PY2023");
-    let hcc_labels = match read_hcc_labels(&(cms_dir + "/V28115L3.txt")) {
-        Ok(map) => map,
-        Err(_err) => BTreeMap::new(),
+    // --mode native|execute produce an unproven preview of the scores, prove is the default
+    let mode = match parse_scoring_mode() {
+        Some(mode) => mode,
+        None => {
+            eprintln!("Usage: zero-raf-host [--mode prove|execute|native]");
+            std::process::exit(2);
+        }
     };
 
-    let hcc_hiers = match read_hier("./CMS-Data/PY2023/V28115H1.TXT") {
-        Ok(map) => map,
-        Err(_err) => BTreeMap::new(),
+    let cms_dir = get_cms_data_dir(PERFORMANCE_YEAR);
+    let _public_inputs = match load_public_inputs(&cms_dir) {
+        Ok(public_inputs) => public_inputs,
+        Err(err) => {
+            eprintln!("Unable to load CMS tables from {}: {}", cms_dir, err);
+            eprintln!("Refusing to prove a RAF score without complete public inputs.");
+            std::process::exit(1);
+        }
     };
 
-    let hcc_coeffs = match read_hcc_coefficients("./CMS-Data/PY2023/C2824T2N.csv") {
-        Ok(map) => map,
-        Err(_err) => BTreeMap::new(),
-    };
-
-    let dx_to_cc = match read_dx_to_cc("./CMS-Data/PY2023/F2823T2N_FY22FY23.TXT") {
-        Ok(map) => map,
-        Err(_err) => BTreeMap::new(),
-    };
+    if let Err(issues) = _public_inputs.validate() {
+        eprintln!("CMS tables in {} are inconsistent ({} issues):", cms_dir, issues.len());
+        for issue in &issues {
+            eprintln!("  - {}", issue);
+        }
+        std::process::exit(1);
+    }
 
-    let _public_inputs = PublicRAFInputs {
-        hcc_coefficients: hcc_coeffs,
-        hcc_hierarchies: hcc_hiers,
-        hcc_labels: hcc_labels,
-        dx_to_cc: dx_to_cc,
-        norm_factor: 1.0,
-    };
+    // Compile the CMS tables into the integer-indexed word format read by the Guest
+    let _public_tables = CompiledRAFTables::compile(&_public_inputs)?;
+
+    // Commit to the full dx_to_cc table, the Guest only receives the rows each patient needs
+    let _dx_table = DxMerkleTable::from_dx_to_cc::<Sha2Hasher>(&_public_inputs.dx_to_cc);
+    println!("dx_to_cc Merkle root: {}", hash_to_hex(&_dx_table.commitment().root));
+
+    // A synthetic beneficiary drawn from the real dx_to_cc table until patient files are read
+    let _private_input = SyntheticPopulation::new(&_public_inputs.dx_to_cc, PopulationProfile::default(), 42)?.next_beneficiary();
+    println!("Scoring synthetic beneficiary: age {}, {} diagnosis codes", _private_input.age, _private_input.diagnosis_codes.len());
+
+    // Codes that are dropped or unmapped are only reported here, never committed by the Guest
+    let dx_report = validate_diagnosis_codes(&_private_input.diagnosis_codes, &_public_inputs.dx_to_cc);
+    if dx_report.has_issues() || dx_report.duplicates > 0 {
+        eprintln!("Diagnosis codes: {} scored, {} duplicates merged, {} issues:",
+                  dx_report.codes.len(), dx_report.duplicates, dx_report.issues.len());
+        for issue in &dx_report.issues {
+            eprintln!("  - {}", issue);
+        }
+    }
 
-    let _private_input = PrivateRAFInput {
-        diagnosis_codes: vec!["A1234".to_string(), "B1234".to_string()],
-        age: 70,
-        sex: "M".to_string(),
-        eligibility_code: "CNA".to_string(),
-        entitlement_reason_code: "1".to_string(),
-        medicaid_status: false,
-        long_term_institutionalized: false,
-    };
+    if mode == ScoringMode::Native {
+        let words = _public_tables.to_words();
+        let journal = score_natively(CompiledTablesView::new(&words)?, &_public_inputs.interactions, &_dx_table, &_private_input)?;
+        write_preview(UnprovenJournal::new(mode, journal))?;
+        return Ok(());
+    }
 
     println!("About to serialize private & public inputs");
 
-    let session: Session = execute_raf_scoring(&_private_input, &_public_inputs);
+    let session: Session = execute_raf_scoring(&_private_input, &_public_tables, &_public_inputs.interactions, &_dx_table);
 
     println!("Number of segments in session: {}", session.segments.len());
 
@@ -82,30 +106,58 @@ fn main() -> Result<(), Box<dyn Error>> {
     }
     println!("Total cycles: {}", total_cycles);
 
+    if mode == ScoringMode::Execute {
+        let journal: Journal = from_slice(&session.journal)?;
+        write_preview(UnprovenJournal::new(mode, journal))?;
+        return Ok(());
+    }
+
     let receipt: SessionReceipt = prove_raf_scoring(session);
-    verify_raf_scoring(receipt);
+    verify_raf_scoring(&receipt);
 
-    // TODO: Implement code for transmitting or serializing the receipt for other parties to verify here
+    write_receipt_bundle(receipt)?;
     Ok(())
 }
 
-fn execute_raf_scoring(private_inputs: &PrivateRAFInput, public_inputs: &PublicRAFInputs) -> Session {
+// Reads `--mode <mode>` from the command line, defaulting to proving
+fn parse_scoring_mode() -> Option<ScoringMode> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => Some(ScoringMode::Prove),
+        [flag, mode] if flag == "--mode" => ScoringMode::parse(mode),
+        _ => None,
+    }
+}
 
-    // let mut prover =
-    //     Prover::new(ZERO_RAF_ELF).expect("Prover should be constructed from valid ELF binary");
+/*
+    Writes scores computed without a proof to ./zero-raf-preview.json. The file and the
+    console output both say the scores are unproven so they are not submitted by mistake.
+ */
+fn write_preview(preview: UnprovenJournal) -> Result<(), Box<dyn Error>> {
 
-    let env = ExecutorEnv::builder()
-                .add_input(&to_vec(public_inputs).unwrap())
-                .add_input(&to_vec(private_inputs).unwrap())
-                .build();
+    println!("{}", preview.warning);
+    for (score, value) in &preview.journal.raf_scores {
+        println!("  {}: {}", score, value);
+    }
+
+    let output_path = PathBuf::from("./zero-raf-preview.json");
+    fs::write(&output_path, serde_json::to_string_pretty(&preview)?)?;
+    println!("Wrote unproven preview to {}", output_path.display());
 
-    // Make the Executor.
-    let mut exec = Executor::from_elf(env, ZERO_RAF_ELF).unwrap();
+    Ok(())
+}
 
-    println!("Created executor for the guest program.");
+fn execute_raf_scoring(private_inputs: &PrivateRAFInput, public_tables: &CompiledRAFTables, interactions: &InteractionDefinitions, dx_table: &DxMerkleTable) -> Session {
+
+    // let mut prover =
+    //     Prover::new(ZERO_RAF_ELF).expect("Prover should be constructed from valid ELF binary");
+
+    // The Guest inputs are laid out by the host library, shared with the roster prover
+    let words = public_tables.to_words();
+    let tables = ScoringTables { words: &words, dx_table, interactions };
 
     // Run the executor to produce a session.
-    let session = exec.run().unwrap();
+    let session = execute_member(tables, private_inputs, None).unwrap();
 
     println!("Executed guest code. Returning session.");
 
@@ -120,16 +172,11 @@ fn prove_raf_scoring(session: Session) -> SessionReceipt {
 
     println!("Proved the session. Returning receipt.");
 
-    let output_path = PathBuf::from("./zero-raf-receipt.txt");
-    
-    let receipt_json = serde_json::to_string(&receipt).unwrap();
-    fs::write(output_path, receipt_json.to_string()).expect("Unable to write file");
-
     return receipt;
 
 }
 
-fn verify_raf_scoring(receipt: SessionReceipt) {
+fn verify_raf_scoring(receipt: &SessionReceipt) {
 
     // Optional: Verify receipt to confirm that recipients will also be able to verify your receipt
     let verified = receipt.verify(ZERO_RAF_ID).expect(
@@ -140,6 +187,20 @@ fn verify_raf_scoring(receipt: SessionReceipt) {
 
 }
 
+/*
+    Writes the receipt as a self-describing bundle to ./zero-raf-receipt.bundle, with a JSON
+    view of it in ./zero-raf-receipt.json, see `zero_raf_host::bundle`.
+ */
+fn write_receipt_bundle(receipt: SessionReceipt) -> Result<(), Box<dyn Error>> {
+
+    let bundle = ReceiptBundle::new(receipt, ZERO_RAF_ID, PERFORMANCE_YEAR).map_err(|e| e.to_string())?;
+    fs::write("./zero-raf-receipt.bundle", bundle.to_bytes().map_err(|e| e.to_string())?)?;
+    fs::write("./zero-raf-receipt.json", bundle.to_json().map_err(|e| e.to_string())?)?;
+    println!("Wrote receipt bundle to ./zero-raf-receipt.bundle and its JSON view to ./zero-raf-receipt.json");
+
+    Ok(())
+}
+
 
 #[test]
 fn can_send_to_prover() {
@@ -193,6 +254,59 @@ fn can_serialize_private_input() {
     };
 
     println!("About to serialize private inputs");
-    let _input_data = &to_vec(&private_input).unwrap();
+    let _input_data = &risc0_zkvm::serde::to_vec(&private_input).unwrap();
+
+}
+#[test]
+fn refuses_missing_cms_tables() {
+    use zero_raf_core::error::RAFDataError;
+    let err = load_public_inputs("./CMS-Data/PY1999").unwrap_err();
+    assert!(matches!(err, RAFDataError::Open { .. }));
+    assert!(err.path().ends_with("V28115L3.TXT"));
+}
 
-}
\ No newline at end of file
+/*
+    Executes the Guest on every person of the regression corpus (core/fixtures/regression_corpus)
+    and diffs each committed score against the hand-derived expected values. This catches drift
+    between the Guest and those values; it is not a check against SAS output. The regression
+    variables are not committed, so the variable diff is native-only: it scores the same inputs
+    with the engine the Guest runs, and its mismatches are labelled "(native)". Proving is
+    skipped: the receipt does not change the journal.
+*/
+#[test]
+fn guest_matches_regression_corpus() {
+    use zero_raf_core::corpus::{diff_scores, diff_variables, read_regression_corpus};
+    use zero_raf_core::engine::ScoringContext;
+
+    let public_inputs = load_public_inputs(&get_cms_data_dir("PY2023")).unwrap();
+    let public_tables = CompiledRAFTables::compile(&public_inputs).unwrap();
+    let words = public_tables.to_words();
+    let context = ScoringContext::new(CompiledTablesView::new(&words).unwrap(), &public_inputs.interactions);
+    let dx_table = DxMerkleTable::from_dx_to_cc::<Sha2Hasher>(&public_inputs.dx_to_cc);
+
+    let corpus_dir = format!("{}/../core/fixtures/regression_corpus", env!("CARGO_MANIFEST_DIR"));
+    let mut mismatches = vec![];
+    for case in read_regression_corpus(&corpus_dir).unwrap() {
+        let session = execute_raf_scoring(&case.input, &public_tables, &public_inputs.interactions, &dx_table);
+        let journal: Journal = from_slice(&session.journal).unwrap();
+        assert_eq!(journal.dx_table, dx_table.commitment());
+        mismatches.extend(diff_scores(&case, &journal.raf_scores));
+
+        let mapped_hccs: Vec<String> = case
+            .input
+            .diagnosis_codes
+            .iter()
+            .filter_map(|dx| public_inputs.dx_to_cc.get(dx))
+            .flatten()
+            .cloned()
+            .collect();
+        let scores = context.score(&case.input, &mapped_hccs);
+        let native = diff_variables(&case, &scores.segment_variables(&case.input.eligibility_code), &public_inputs.model);
+        mismatches.extend(native.into_iter().map(|mut x| {
+            x.name = format!("{} (native)", x.name);
+            x
+        }));
+    }
+    let report: Vec<String> = mismatches.iter().map(|x| x.to_string()).collect();
+    assert!(report.is_empty(), "{}", report.join("\n"));
+}
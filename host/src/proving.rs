@@ -0,0 +1,139 @@
+use zero_raf_core::chunks::{chunk_ranges, run_chunks, ChunkProgress};
+use zero_raf_core::diagnosis::normalize_diagnosis_codes;
+use zero_raf_core::dx_table::DxMerkleTable;
+use zero_raf_core::interactions::InteractionDefinitions;
+use zero_raf_core::{Journal, PrivateRAFInput};
+use zero_raf_methods::{ZERO_RAF_ELF, ZERO_RAF_ID};
+use risc0_zkvm::serde::{from_slice, to_vec};
+use risc0_zkvm::{Executor, ExecutorEnv, Session, SessionReceipt};
+use std::error::Error;
+
+// Chunked, parallel proving of rosters with the scoring Guest.
+//
+// Every member is executed and proven in a session of their own, as `prove_raf_scoring` does
+// for the single member of `main`. The roster is split into chunks that a pool of workers
+// proves concurrently (see `zero_raf_core::chunks`), and each session is split into segments
+// of at most 2^segment_limit_po2 cycles. Smaller segments need less memory per worker, which
+// is what usually bounds the worker count on a CPU-only machine.
+//
+// `execute_member_logged` is the only place that lays out the inputs of the scoring Guest;
+// `main`, the benchmark and the other binaries all go through it.
+
+/// Errors of the zkVM and of decoding journals, sendable across workers
+pub type ProvingError = Box<dyn Error + Send + Sync>;
+
+/// Public inputs every member of a roster is scored with
+#[derive(Clone, Copy)]
+pub struct ScoringTables<'a> {
+    // Compiled public tables, see `CompiledRAFTables::to_words`
+    pub words: &'a [u32],
+
+    pub dx_table: &'a DxMerkleTable,
+    pub interactions: &'a InteractionDefinitions,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProvingOptions {
+    // Chunks proven at the same time. Each worker holds a whole segment in memory while
+    // proving, so size this by memory rather than cores: divide the memory free for proving
+    // by what one worker peaks at for the chosen `segment_limit_po2`. Defaults to 1.
+    pub workers: usize,
+
+    // Members per chunk
+    pub chunk_size: usize,
+
+    // Upper bound on the segment size as a power of two, the zkVM default when None
+    pub segment_limit_po2: Option<u32>,
+
+    // Only execute the Guest, the journals are not backed by receipts
+    pub execute_only: bool,
+}
+
+impl Default for ProvingOptions {
+    fn default() -> Self {
+        ProvingOptions {
+            workers: 1,
+            chunk_size: 10,
+            segment_limit_po2: None,
+            execute_only: false,
+        }
+    }
+}
+
+/// Outcome for one member of the roster
+#[derive(Debug)]
+pub struct MemberResult {
+    // Position in the roster
+    pub member: usize,
+
+    pub journal: Journal,
+
+    // Verified against ZERO_RAF_ID, None when only executed
+    pub receipt: Option<SessionReceipt>,
+}
+
+/// Runs the scoring Guest for one member without proving
+pub fn execute_member(tables: ScoringTables, private_input: &PrivateRAFInput, segment_limit_po2: Option<u32>) -> Result<Session, ProvingError> {
+    execute_member_logged(tables, private_input, segment_limit_po2, None)
+}
+
+/*
+    `execute_member`, with the Guest stdout written to `phase_log` when given. The Guest only
+    emits its phase markers then, so runs outside the benchmark do not pay their cycles.
+*/
+pub fn execute_member_logged(
+    tables: ScoringTables,
+    private_input: &PrivateRAFInput,
+    segment_limit_po2: Option<u32>,
+    phase_log: Option<&mut Vec<u8>>,
+) -> Result<Session, ProvingError> {
+
+    let mut builder = ExecutorEnv::builder();
+    builder
+        .add_input(&to_vec(&phase_log.is_some())?)
+        .add_input(&to_vec(&tables.words)?)
+        .add_input(&to_vec(&tables.dx_table.commitment())?)
+        .add_input(&to_vec(tables.interactions)?)
+        .add_input(&to_vec(private_input)?)
+        .add_input(&to_vec(&tables.dx_table.lookups(&normalize_diagnosis_codes(&private_input.diagnosis_codes)))?);
+    if let Some(po2) = segment_limit_po2 {
+        builder.segment_limit_po2(po2);
+    }
+    if let Some(stdout) = phase_log {
+        builder.stdout(stdout);
+    }
+    let mut exec = Executor::from_elf(builder.build(), ZERO_RAF_ELF)?;
+    Ok(exec.run()?)
+}
+
+/// Executes and, unless `execute_only`, proves one member and verifies the receipt
+pub fn prove_member(tables: ScoringTables, member: usize, private_input: &PrivateRAFInput, options: &ProvingOptions) -> Result<MemberResult, ProvingError> {
+
+    let session = execute_member(tables, private_input, options.segment_limit_po2)?;
+    if options.execute_only {
+        let journal = from_slice(&session.journal)?;
+        return Ok(MemberResult { member, journal, receipt: None });
+    }
+
+    let receipt = session.prove()?;
+    receipt.verify(ZERO_RAF_ID)?;
+    Ok(MemberResult { member, journal: from_slice(&receipt.journal)?, receipt: Some(receipt) })
+}
+
+/*
+    Proves every member of the roster, `options.workers` chunks at a time, calling `progress`
+    as chunks complete. Results are in roster order. Proving stops at the first member that
+    fails, whose error is returned.
+*/
+pub fn prove_roster<P>(tables: ScoringTables, roster: &[PrivateRAFInput], options: &ProvingOptions, progress: P) -> Result<Vec<MemberResult>, ProvingError>
+where
+    P: Fn(&ChunkProgress) + Sync,
+{
+    let chunks = chunk_ranges(roster.len(), options.chunk_size);
+    let results = run_chunks(&chunks, options.workers, |_, members| {
+        members
+            .map(|member| prove_member(tables, member, &roster[member], options))
+            .collect::<Result<Vec<MemberResult>, ProvingError>>()
+    }, progress)?;
+    Ok(results.into_iter().flatten().collect())
+}
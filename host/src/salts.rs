@@ -0,0 +1,41 @@
+use zero_raf_core::merkle::Hash;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+// Salts of member input commitments (`radv::input_commitment`).
+//
+// A salt is all that keeps a commitment from being brute forced over plausible inputs, so it
+// is drawn from the operating system's CSPRNG, never from the seed of a synthetic roster. The
+// plan must keep the salts to open the commitments later: they are written to a file of their
+// own, readable by the owner only, next to the public outputs. That file is private and must
+// not be shared with the proofs.
+
+/// A member commitment and the salt that opens it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemberSalt {
+    pub input_commitment: Hash,
+    pub salt: Hash,
+}
+
+/// 32 bytes from the operating system's CSPRNG
+pub fn random_salt() -> Result<Hash, getrandom::Error> {
+    let mut salt: Hash = [0; 32];
+    getrandom::getrandom(&mut salt)?;
+    Ok(salt)
+}
+
+/// Writes the salts as JSON to a new file only the owner can read, refusing to overwrite one
+pub fn write_salts(path: &Path, salts: &[MemberSalt]) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(serde_json::to_string_pretty(salts)?.as_bytes())?;
+    file.sync_all()
+}
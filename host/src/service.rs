@@ -0,0 +1,402 @@
+use zero_raf_core::chunks::ChunkProgress;
+use zero_raf_core::{Journal, PrivateRAFInput};
+use crate::proving::{prove_roster, ProvingError, ProvingOptions, ScoringTables};
+use risc0_zkvm::SessionReceipt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use tiny_http::{Header, Request, Response, Server};
+
+// Local HTTP service to submit scoring jobs and fetch their results.
+//
+//     POST /jobs                 body `JobRequest`, answers 202 with the `JobStatus` of the job
+//     GET  /jobs/<id>            `JobStatus`
+//     GET  /jobs/<id>/journals   journals of the members in roster order, once the job is done
+//     GET  /jobs/<id>/receipts   their receipts, verified against ZERO_RAF_ID
+//
+// Jobs are proven one after the other by `run_jobs` on a background thread, with the same
+// `proving::prove_roster` the roster binary uses and the worker pool of the server's
+// `ProvingOptions`. A body over `MAX_BODY_BYTES` is refused with 413 before it is read in
+// full, and at most `MAX_QUEUED_JOBS` jobs wait for the prover; beyond that a submitted job
+// is marked failed and answered with 503. Where jobs are kept is up to the `JobStore`:
+// `MemoryJobStore` forgets them on restart and `DirectoryJobStore` keeps them on disk. Rosters
+// hold PHI, so the server binds to localhost unless told otherwise and rosters are never
+// written to the store.
+
+// Largest POST /jobs body read, about 50,000 members with a few dozen diagnosis codes each
+pub const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+// Jobs waiting for the prover, each holds its roster in memory until it is proven
+pub const MAX_QUEUED_JOBS: usize = 8;
+
+/// Body of POST /jobs
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobRequest {
+    pub roster: Vec<PrivateRAFInput>,
+
+    // Only execute the Guest, the journals are not backed by receipts
+    #[serde(default)]
+    pub execute_only: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Queued,
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub id: String,
+    pub state: JobState,
+    pub members: usize,
+    pub members_done: usize,
+
+    // False for execute_only jobs, whose journals are unproven
+    pub proven: bool,
+
+    // Why the job failed
+    pub error: Option<String>,
+}
+
+/// Where the status and results of jobs are kept
+pub trait JobStore: Send + Sync {
+    // Records a new queued job
+    fn create(&self, members: usize, proven: bool) -> Result<JobStatus, ProvingError>;
+
+    fn status(&self, id: &str) -> Result<Option<JobStatus>, ProvingError>;
+    fn update(&self, status: &JobStatus) -> Result<(), ProvingError>;
+    fn save_results(&self, id: &str, journals: &[Journal], receipts: &[SessionReceipt]) -> Result<(), ProvingError>;
+
+    // Results as JSON, None until they are saved
+    fn journals(&self, id: &str) -> Result<Option<String>, ProvingError>;
+    fn receipts(&self, id: &str) -> Result<Option<String>, ProvingError>;
+}
+
+fn _queued(id: usize, members: usize, proven: bool) -> JobStatus {
+    JobStatus { id: format!("{:06}", id), state: JobState::Queued, members, members_done: 0, proven, error: None }
+}
+
+/// Jobs kept in memory, lost when the server stops
+#[derive(Default)]
+pub struct MemoryJobStore {
+    // Status, journals and receipts of each job
+    jobs: Mutex<BTreeMap<String, (JobStatus, Option<String>, Option<String>)>>,
+}
+
+impl JobStore for MemoryJobStore {
+    fn create(&self, members: usize, proven: bool) -> Result<JobStatus, ProvingError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let status = _queued(jobs.len() + 1, members, proven);
+        jobs.insert(status.id.clone(), (status.clone(), None, None));
+        Ok(status)
+    }
+
+    fn status(&self, id: &str) -> Result<Option<JobStatus>, ProvingError> {
+        Ok(self.jobs.lock().unwrap().get(id).map(|x| x.0.clone()))
+    }
+
+    fn update(&self, status: &JobStatus) -> Result<(), ProvingError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.get_mut(&status.id).ok_or(format!("unknown job {}", status.id))?;
+        job.0 = status.clone();
+        Ok(())
+    }
+
+    fn save_results(&self, id: &str, journals: &[Journal], receipts: &[SessionReceipt]) -> Result<(), ProvingError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.get_mut(id).ok_or(format!("unknown job {}", id))?;
+        job.1 = Some(serde_json::to_string(journals)?);
+        job.2 = Some(serde_json::to_string(receipts)?);
+        Ok(())
+    }
+
+    fn journals(&self, id: &str) -> Result<Option<String>, ProvingError> {
+        Ok(self.jobs.lock().unwrap().get(id).and_then(|x| x.1.clone()))
+    }
+
+    fn receipts(&self, id: &str) -> Result<Option<String>, ProvingError> {
+        Ok(self.jobs.lock().unwrap().get(id).and_then(|x| x.2.clone()))
+    }
+}
+
+/*
+    Jobs kept under a directory, one sub directory per job holding status.json,
+    journals.json and receipts.json. Rosters are not stored, so jobs still queued or
+    running when the server stops are marked failed when it starts again.
+*/
+pub struct DirectoryJobStore {
+    root: PathBuf,
+
+    // Serializes the allocation of job IDs
+    next_id: Mutex<usize>,
+}
+
+impl DirectoryJobStore {
+    pub fn open(root: PathBuf) -> Result<Self, ProvingError> {
+        fs::create_dir_all(&root)?;
+        let mut ids = vec![];
+        for entry in fs::read_dir(&root)? {
+            let name = entry?.file_name().to_string_lossy().to_string();
+            if let Ok(id) = name.parse::<usize>() {
+                ids.push((id, name));
+            }
+        }
+
+        let next_id = ids.iter().map(|x| x.0 + 1).max().unwrap_or(1);
+        let store = DirectoryJobStore { root, next_id: Mutex::new(next_id) };
+        for (_, id) in &ids {
+            if let Some(mut status) = store.status(id)? {
+                if status.state == JobState::Queued || status.state == JobState::Running {
+                    status.state = JobState::Failed;
+                    status.error = Some("the server stopped before the job completed".to_string());
+                    store.update(&status)?;
+                }
+            }
+        }
+        Ok(store)
+    }
+
+    fn file(&self, id: &str, name: &str) -> PathBuf {
+        self.root.join(id).join(name)
+    }
+
+    fn read(&self, id: &str, name: &str) -> Result<Option<String>, ProvingError> {
+        let path = self.file(id, name);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(fs::read_to_string(path)?))
+    }
+}
+
+impl JobStore for DirectoryJobStore {
+    fn create(&self, members: usize, proven: bool) -> Result<JobStatus, ProvingError> {
+        let mut next_id = self.next_id.lock().unwrap();
+        let status = _queued(*next_id, members, proven);
+        fs::create_dir(self.root.join(&status.id))?;
+        self.update(&status)?;
+        *next_id += 1;
+        Ok(status)
+    }
+
+    fn status(&self, id: &str) -> Result<Option<JobStatus>, ProvingError> {
+        match self.read(id, "status.json")? {
+            Some(json) => Ok(Some(serde_json::from_str(&json)?)),
+            None => Ok(None),
+        }
+    }
+
+    // Replaced through a temporary file so a status is never read half written
+    fn update(&self, status: &JobStatus) -> Result<(), ProvingError> {
+        let temporary = self.file(&status.id, "status.json.tmp");
+        fs::write(&temporary, serde_json::to_string_pretty(status)?)?;
+        fs::rename(&temporary, self.file(&status.id, "status.json"))?;
+        Ok(())
+    }
+
+    fn save_results(&self, id: &str, journals: &[Journal], receipts: &[SessionReceipt]) -> Result<(), ProvingError> {
+        fs::write(self.file(id, "journals.json"), serde_json::to_string(journals)?)?;
+        fs::write(self.file(id, "receipts.json"), serde_json::to_string(receipts)?)?;
+        Ok(())
+    }
+
+    fn journals(&self, id: &str) -> Result<Option<String>, ProvingError> {
+        self.read(id, "journals.json")
+    }
+
+    fn receipts(&self, id: &str) -> Result<Option<String>, ProvingError> {
+        self.read(id, "receipts.json")
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Route<'a> {
+    Submit,
+    Status(&'a str),
+    Journals(&'a str),
+    Receipts(&'a str),
+    NotFound,
+}
+
+// Job IDs are digits only, so they can never escape the directory of a `DirectoryJobStore`
+fn route<'a>(method: &str, url: &'a str) -> Route<'a> {
+    let path = url.split('?').next().unwrap_or("");
+    let parts: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let valid_id = |id: &str| !id.is_empty() && id.bytes().all(|x| x.is_ascii_digit());
+    match (method, parts.as_slice()) {
+        ("POST", ["jobs"]) => Route::Submit,
+        ("GET", ["jobs", id]) if valid_id(id) => Route::Status(id),
+        ("GET", ["jobs", id, "journals"]) if valid_id(id) => Route::Journals(id),
+        ("GET", ["jobs", id, "receipts"]) if valid_id(id) => Route::Receipts(id),
+        _ => Route::NotFound,
+    }
+}
+
+/// Reads a request body of at most `limit` bytes, None when it is larger
+pub fn read_body(reader: impl Read, body_length: Option<usize>, limit: usize) -> Result<Option<Vec<u8>>, ProvingError> {
+    if matches!(body_length, Some(x) if x > limit) {
+        return Ok(None);
+    }
+
+    // The declared length is not trusted, a chunked or lying body stops one byte past the limit
+    let mut body = vec![];
+    reader.take(limit as u64 + 1).read_to_end(&mut body)?;
+    if body.len() > limit {
+        return Ok(None);
+    }
+    Ok(Some(body))
+}
+
+// Status code and JSON body answering a request
+fn handle(request: &mut Request, store: &dyn JobStore, queue: &SyncSender<(String, JobRequest)>) -> Result<(u16, String), ProvingError> {
+
+    let method = request.method().as_str().to_string();
+    let url = request.url().to_string();
+    let error = |message: &str| serde_json::json!({ "error": message }).to_string();
+    let results = |json: Option<String>, status: Option<JobStatus>| match (json, status) {
+        (Some(json), _) => (200, json),
+        (None, Some(_)) => (409, error("the job has no results yet")),
+        (None, None) => (404, error("unknown job")),
+    };
+
+    Ok(match route(&method, &url) {
+        Route::Submit => {
+            let body_length = request.body_length();
+            let body = match read_body(request.as_reader(), body_length, MAX_BODY_BYTES)? {
+                Some(body) => body,
+                None => return Ok((413, error(&format!("the body is larger than {} bytes", MAX_BODY_BYTES)))),
+            };
+            let job: JobRequest = match serde_json::from_slice(&body) {
+                Ok(job) => job,
+                Err(e) => return Ok((400, error(&format!("invalid job: {}", e)))),
+            };
+            if job.roster.is_empty() {
+                return Ok((400, error("the roster has no members")));
+            }
+            let mut status = store.create(job.roster.len(), !job.execute_only)?;
+            match queue.try_send((status.id.clone(), job)) {
+                Ok(()) => (202, serde_json::to_string(&status)?),
+                Err(TrySendError::Full(_)) => {
+                    status.state = JobState::Failed;
+                    status.error = Some(format!("{} jobs are already queued, submit again later", MAX_QUEUED_JOBS));
+                    store.update(&status)?;
+                    (503, serde_json::to_string(&status)?)
+                }
+                Err(TrySendError::Disconnected(_)) => return Err("the proving thread has stopped".into()),
+            }
+        }
+        Route::Status(id) => match store.status(id)? {
+            Some(status) => (200, serde_json::to_string(&status)?),
+            None => (404, error("unknown job")),
+        },
+        Route::Journals(id) => results(store.journals(id)?, store.status(id)?),
+        Route::Receipts(id) => results(store.receipts(id)?, store.status(id)?),
+        Route::NotFound => (404, error("not found")),
+    })
+}
+
+/// Answers requests on `addr` until the process stops, queueing submitted jobs on `queue`
+pub fn serve(addr: &str, store: Arc<dyn JobStore>, queue: SyncSender<(String, JobRequest)>) -> Result<(), ProvingError> {
+
+    let server = Server::http(addr)?;
+    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+    for mut request in server.incoming_requests() {
+        let (code, body) = handle(&mut request, store.as_ref(), &queue).unwrap_or_else(|e| {
+            (500, serde_json::json!({ "error": e.to_string() }).to_string())
+        });
+        let response = Response::from_string(body).with_status_code(code).with_header(content_type.clone());
+        if let Err(e) = request.respond(response) {
+            eprintln!("Unable to answer a request: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/*
+    Proves the queued jobs one at a time until the queue is closed, recording progress,
+    results and failures in the store.
+*/
+pub fn run_jobs(store: Arc<dyn JobStore>, queue: Receiver<(String, JobRequest)>, tables: ScoringTables, options: &ProvingOptions) {
+
+    for (id, job) in queue {
+        let mut status = match store.status(&id) {
+            Ok(Some(status)) => status,
+            _ => continue,
+        };
+        status.state = JobState::Running;
+        let report = |status: &JobStatus| {
+            if let Err(e) = store.update(status) {
+                eprintln!("Unable to update job {}: {}", status.id, e);
+            }
+        };
+        report(&status);
+
+        let options = ProvingOptions { execute_only: job.execute_only, ..options.clone() };
+        let running = status.clone();
+        let outcome = prove_roster(tables, &job.roster, &options, |progress: &ChunkProgress| {
+            report(&JobStatus { members_done: progress.members_done, ..running.clone() })
+        })
+        .and_then(|results| {
+            let journals: Vec<Journal> = results.iter().map(|x| x.journal.clone()).collect();
+            let receipts: Vec<SessionReceipt> = results.into_iter().filter_map(|x| x.receipt).collect();
+            store.save_results(&id, &journals, &receipts)
+        });
+
+        match outcome {
+            Ok(()) => {
+                status.state = JobState::Done;
+                status.members_done = status.members;
+            }
+            Err(e) => {
+                status.state = JobState::Failed;
+                status.error = Some(e.to_string());
+            }
+        }
+        report(&status);
+    }
+}
+
+#[test]
+fn can_route_job_requests() {
+    assert_eq!(route("POST", "/jobs"), Route::Submit);
+    assert_eq!(route("GET", "/jobs/000012"), Route::Status("000012"));
+    assert_eq!(route("GET", "/jobs/000012/journals?pretty"), Route::Journals("000012"));
+    assert_eq!(route("GET", "/jobs/000012/receipts/"), Route::Receipts("000012"));
+    assert_eq!(route("GET", "/jobs/..%2F..%2Fetc/receipts"), Route::NotFound);
+    assert_eq!(route("DELETE", "/jobs/000012"), Route::NotFound);
+}
+
+#[test]
+fn refuses_oversized_bodies() {
+    let body = "x".repeat(10);
+    assert_eq!(read_body(body.as_bytes(), Some(10), 10).unwrap(), Some(body.clone().into_bytes()));
+    assert_eq!(read_body(body.as_bytes(), Some(11), 10).unwrap(), None);
+
+    // A body longer than it declared, or without a length, is cut off past the limit
+    assert_eq!(read_body(body.as_bytes(), Some(5), 9).unwrap(), None);
+    assert_eq!(read_body(body.as_bytes(), None, 9).unwrap(), None);
+}
+
+#[test]
+fn can_keep_jobs_in_memory() {
+    let store = MemoryJobStore::default();
+    let mut status = store.create(3, true).unwrap();
+    assert_eq!((status.id.as_str(), status.state), ("000001", JobState::Queued));
+    assert_eq!(store.create(1, false).unwrap().id, "000002");
+
+    status.state = JobState::Done;
+    store.update(&status).unwrap();
+    assert_eq!(store.status("000001").unwrap(), Some(status));
+    assert_eq!(store.journals("000001").unwrap(), None);
+    store.save_results("000001", &[], &[]).unwrap();
+    assert_eq!(store.journals("000001").unwrap(), Some("[]".to_string()));
+    assert!(store.status("000003").unwrap().is_none());
+}
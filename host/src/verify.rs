@@ -0,0 +1,328 @@
+use zero_raf_core::compiled::public_inputs_digest;
+use zero_raf_core::merkle::{hash_from_hex, hash_to_hex, Hash, Sha2Hasher};
+use zero_raf_core::Journal;
+use crate::bundle::ReceiptBundle;
+use crate::proving::ProvingError;
+use crate::service::read_body;
+use risc0_zkvm::serde::from_slice;
+use risc0_zkvm::SessionReceipt;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+// Verification of scoring receipts for parties that do not run the prover.
+//
+// A `VerificationPolicy` lists the image IDs of the scoring Guest builds and the CMS releases
+// an auditor accepts. Each release is one `PolicyEntry`: a performance year, a model version,
+// the digest of the manifest of CMS files (`TableManifest::digest`) and the
+// `compiled::public_inputs_digest` of the tables compiled from them, which folds the tables
+// digest, the dx_to_cc root and the interaction definitions a journal names. A bundle must
+// match all four of a single entry, so it cannot be relabelled with the year of another
+// entry. `verify_receipt` checks a serialized `SessionReceipt`, which carries no metadata,
+// against the public inputs digests of the entries, and `verify_bundle` a `ReceiptBundle`.
+// Both always answer with a `Verdict` saying whether the receipt passed and why, with the
+// decoded `Journal` when the receipt itself verified.
+
+// Largest receipt or bundle the verifier reads from a request, room for a scoring receipt
+// of several segments serialized as JSON
+pub const MAX_RECEIPT_BYTES: usize = 8 * 1024 * 1024;
+
+/// Image IDs and CMS releases a verifier accepts
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VerificationPolicy {
+    pub image_ids: Vec<[u32; 8]>,
+    pub entries: Vec<PolicyEntry>,
+}
+
+/// A CMS release a verifier accepts, matched as a whole
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyEntry {
+    // e.g. PY2023
+    pub performance_year: String,
+
+    // See `utils::MODEL_VERSION`
+    pub model_version: String,
+
+    // `TableManifest::digest` of the CMS files the tables were compiled from
+    pub table_manifest_digest: Hash,
+
+    // `compiled::public_inputs_digest` of the tables, dx_to_cc table and interactions
+    pub public_inputs_digest: Hash,
+}
+
+// The policy file, every ID and digest as hex
+#[derive(Serialize, Deserialize)]
+struct PolicyFile {
+    image_ids: Vec<String>,
+    entries: Vec<PolicyFileEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PolicyFileEntry {
+    performance_year: String,
+    model_version: String,
+    table_manifest_digest: String,
+    public_inputs_digest: String,
+}
+
+/// Hex of the image ID words in little endian, as RISC Zero prints a `Digest`
+pub fn image_id_to_hex(image_id: &[u32; 8]) -> String {
+    let mut bytes = [0u8; 32];
+    for (i, word) in image_id.iter().enumerate() {
+        bytes[4 * i..4 * i + 4].copy_from_slice(&word.to_le_bytes());
+    }
+    hash_to_hex(&bytes)
+}
+
+pub fn image_id_from_hex(hex: &str) -> Option<[u32; 8]> {
+    let bytes = hash_from_hex(hex)?;
+    let mut image_id = [0u32; 8];
+    for (i, word) in image_id.iter_mut().enumerate() {
+        *word = u32::from_le_bytes([bytes[4 * i], bytes[4 * i + 1], bytes[4 * i + 2], bytes[4 * i + 3]]);
+    }
+    Some(image_id)
+}
+
+impl VerificationPolicy {
+    /// Reads `{"image_ids": [hex, ...], "entries": [{"performance_year": "PY2023", "model_version": "V28 (V2823.115.T2)",
+    /// "table_manifest_digest": hex, "public_inputs_digest": hex}, ...]}`
+    pub fn from_json(json: &str) -> Result<Self, ProvingError> {
+        let file: PolicyFile = serde_json::from_str(json)?;
+        let image_ids = file.image_ids
+            .iter()
+            .map(|x| image_id_from_hex(x).ok_or(format!("invalid image ID {:?}", x)))
+            .collect::<Result<_, _>>()?;
+        let mut entries = vec![];
+        for entry in file.entries {
+            entries.push(PolicyEntry {
+                table_manifest_digest: hash_from_hex(&entry.table_manifest_digest)
+                    .ok_or(format!("invalid table manifest digest {:?}", entry.table_manifest_digest))?,
+                public_inputs_digest: hash_from_hex(&entry.public_inputs_digest)
+                    .ok_or(format!("invalid public inputs digest {:?}", entry.public_inputs_digest))?,
+                performance_year: entry.performance_year,
+                model_version: entry.model_version,
+            });
+        }
+        Ok(VerificationPolicy { image_ids, entries })
+    }
+
+    pub fn to_json(&self) -> Result<String, ProvingError> {
+        let file = PolicyFile {
+            image_ids: self.image_ids.iter().map(image_id_to_hex).collect(),
+            entries: self.entries
+                .iter()
+                .map(|x| PolicyFileEntry {
+                    performance_year: x.performance_year.clone(),
+                    model_version: x.model_version.clone(),
+                    table_manifest_digest: hash_to_hex(&x.table_manifest_digest),
+                    public_inputs_digest: hash_to_hex(&x.public_inputs_digest),
+                })
+                .collect(),
+        };
+        Ok(serde_json::to_string_pretty(&file)?)
+    }
+}
+
+/// Outcome of verifying a receipt against a policy
+#[derive(Debug, Clone, Serialize)]
+pub struct Verdict {
+    pub verified: bool,
+    pub reason: String,
+
+    // Allowed image ID the receipt verified against
+    pub image_id: Option<String>,
+
+    // Present whenever the receipt verified, even when its public inputs are not allowed
+    pub journal: Option<Journal>,
+}
+
+impl Verdict {
+    fn fail(reason: String, image_id: Option<String>, journal: Option<Journal>) -> Self {
+        Verdict { verified: false, reason, image_id, journal }
+    }
+
+    fn pass(verified: String, image_id: String, journal: Journal) -> Self {
+        let reason = format!("{} for image ID {} with allowed public inputs", verified, image_id);
+        Verdict { verified: true, reason, image_id: Some(image_id), journal: Some(journal) }
+    }
+}
+
+/// Reads a receipt or bundle sent to the verifier, None when it is over `MAX_RECEIPT_BYTES`
+pub fn read_receipt(reader: impl Read, body_length: Option<usize>) -> Result<Option<Vec<u8>>, ProvingError> {
+    read_body(reader, body_length, MAX_RECEIPT_BYTES)
+}
+
+/*
+    Checks that `receipt_json` is a `SessionReceipt` proven for one of the allowed image IDs,
+    that its journal is a scoring `Journal` and that the scores were computed with allowed
+    public tables, dx_to_cc table and interactions.
+*/
+pub fn verify_receipt(receipt_json: &str, policy: &VerificationPolicy) -> Verdict {
+
+    let receipt: SessionReceipt = match serde_json::from_str(receipt_json) {
+        Ok(receipt) => receipt,
+        Err(e) => return Verdict::fail(format!("not a serialized SessionReceipt: {}", e), None, None),
+    };
+    let image_id = match policy.image_ids.iter().find(|x| receipt.verify(**x).is_ok()) {
+        Some(image_id) => image_id_to_hex(image_id),
+        None => return Verdict::fail("the receipt does not verify against any allowed image ID".to_string(), None, None),
+    };
+    let journal: Journal = match from_slice(&receipt.journal) {
+        Ok(journal) => journal,
+        Err(e) => return Verdict::fail(format!("the journal is not a scoring Journal: {}", e), Some(image_id), None),
+    };
+    let digest = journal_public_inputs_digest(&journal);
+    if !policy.entries.iter().any(|x| x.public_inputs_digest == digest) {
+        return Verdict::fail(public_inputs_not_allowed(&journal, &digest), Some(image_id), Some(journal));
+    }
+    Verdict::pass("verified".to_string(), image_id, journal)
+}
+
+fn journal_public_inputs_digest(journal: &Journal) -> Hash {
+    return public_inputs_digest::<Sha2Hasher>(&journal.tables_digest, &journal.dx_table, &journal.interactions);
+}
+
+fn public_inputs_not_allowed(journal: &Journal, digest: &Hash) -> String {
+    return format!("scores were computed with public tables {}, dx_to_cc root {} and interactions (public inputs digest {}), which are not allowed",
+                   hash_to_hex(&journal.tables_digest), hash_to_hex(&journal.dx_table.root), hash_to_hex(digest));
+}
+
+/*
+    Checks a bundle as a whole: its image ID is allowed and the receipt verifies against it,
+    the bundled journal is the journal of the receipt, the tables digest of the metadata is
+    the one the journal commits, and the performance year, model version, table manifest and
+    public inputs of the journal are those of a single policy entry.
+*/
+pub fn verify_bundle(bytes: &[u8], policy: &VerificationPolicy) -> Verdict {
+
+    let bundle = match ReceiptBundle::from_bytes(bytes) {
+        Ok(bundle) => bundle,
+        Err(e) => return Verdict::fail(format!("invalid receipt bundle: {}", e), None, None),
+    };
+    let image_id = image_id_to_hex(&bundle.image_id);
+    if !policy.image_ids.contains(&bundle.image_id) {
+        return Verdict::fail(format!("image ID {} of the bundle is not allowed", image_id), None, None);
+    }
+    if let Err(e) = bundle.receipt.verify(bundle.image_id) {
+        return Verdict::fail(format!("the receipt does not verify against the image ID of the bundle: {}", e), None, None);
+    }
+    check_bundle(bundle, policy)
+}
+
+// Checks of `verify_bundle` once the receipt verified
+fn check_bundle(bundle: ReceiptBundle, policy: &VerificationPolicy) -> Verdict {
+    let image_id = image_id_to_hex(&bundle.image_id);
+
+    let journal: Journal = match from_slice(&bundle.receipt.journal) {
+        Ok(journal) => journal,
+        Err(e) => return Verdict::fail(format!("the journal is not a scoring Journal: {}", e), Some(image_id), None),
+    };
+    if journal != bundle.journal {
+        return Verdict::fail("the bundled journal differs from the journal of the receipt".to_string(), Some(image_id), Some(journal));
+    }
+    if journal.tables_digest != bundle.tables_digest {
+        return Verdict::fail("the bundle names other public tables than the journal commits".to_string(), Some(image_id), Some(journal));
+    }
+
+    let digest = journal_public_inputs_digest(&journal);
+    if !policy.entries.iter().any(|x| x.public_inputs_digest == digest) {
+        return Verdict::fail(public_inputs_not_allowed(&journal, &digest), Some(image_id), Some(journal));
+    }
+    let manifest_digest = bundle.table_manifest.digest::<Sha2Hasher>();
+    let allowed = policy.entries.iter().any(|x| {
+        x.performance_year == bundle.performance_year
+            && x.model_version == bundle.model_version
+            && x.table_manifest_digest == manifest_digest
+            && x.public_inputs_digest == digest
+    });
+    if !allowed {
+        let reason = format!("no policy entry allows {} {} with table manifest digest {} and public inputs digest {}",
+                             bundle.performance_year, bundle.model_version, hash_to_hex(&manifest_digest), hash_to_hex(&digest));
+        return Verdict::fail(reason, Some(image_id), Some(journal));
+    }
+    let verified = format!("{} {} bundle created by zero-raf-host {} verified", bundle.performance_year, bundle.model_version, bundle.tool_version);
+    Verdict::pass(verified, image_id, journal)
+}
+
+#[test]
+fn can_read_verification_policies() {
+    let policy = VerificationPolicy {
+        image_ids: vec![[1, 2, 3, 4, 5, 6, 7, u32::MAX]],
+        entries: vec![PolicyEntry {
+            performance_year: "PY2023".to_string(),
+            model_version: "V28".to_string(),
+            table_manifest_digest: [5; 32],
+            public_inputs_digest: [9; 32],
+        }],
+    };
+    assert!(image_id_to_hex(&policy.image_ids[0]).starts_with("0100000002000000"));
+    assert_eq!(VerificationPolicy::from_json(&policy.to_json().unwrap()).unwrap(), policy);
+    assert!(VerificationPolicy::from_json(r#"{"image_ids": ["12"], "entries": []}"#).is_err());
+
+    let verdict = verify_receipt("{}", &policy);
+    assert!(!verdict.verified && verdict.reason.starts_with("not a serialized SessionReceipt"));
+}
+
+/*
+    A PY2023 bundle relabelled PY2024 is refused even though the policy allows PY2024 and the
+    PY2023 tables, because no single entry allows both.
+*/
+#[test]
+fn refuses_relabelled_bundles() {
+    use zero_raf_core::dx_table::DxTableCommitment;
+    use zero_raf_core::interactions::InteractionDefinitions;
+    use zero_raf_core::manifest::{TableFile, TableManifest};
+    use zero_raf_core::utils::MODEL_VERSION;
+    use risc0_zkvm::serde::to_vec;
+    use std::collections::BTreeMap;
+
+    let journal = Journal {
+        raf_scores: BTreeMap::new(),
+        coefficients: BTreeMap::new(),
+        tables_digest: [1; 32],
+        dx_table: DxTableCommitment { root: [2; 32], rows: 3 },
+        interactions: InteractionDefinitions::default(),
+    };
+    let manifest = |sha256: Hash| TableManifest { files: vec![TableFile { name: "V28115H1.TXT".to_string(), sha256 }] };
+    let entry = |performance_year: &str, manifest: &TableManifest, public_inputs_digest: Hash| PolicyEntry {
+        performance_year: performance_year.to_string(),
+        model_version: MODEL_VERSION.to_string(),
+        table_manifest_digest: manifest.digest::<Sha2Hasher>(),
+        public_inputs_digest,
+    };
+    let policy = VerificationPolicy {
+        image_ids: vec![[7; 8]],
+        entries: vec![
+            entry("PY2023", &manifest([4; 32]), journal_public_inputs_digest(&journal)),
+            entry("PY2024", &manifest([5; 32]), [6; 32]),
+        ],
+    };
+    let bundle = |performance_year: &str| ReceiptBundle {
+        performance_year: performance_year.to_string(),
+        model_version: MODEL_VERSION.to_string(),
+        image_id: [7; 8],
+        tables_digest: journal.tables_digest,
+        table_manifest: manifest([4; 32]),
+        created_unix: 0,
+        tool_version: "test".to_string(),
+        journal: journal.clone(),
+        receipt: SessionReceipt {
+            segments: vec![],
+            journal: to_vec(&journal).unwrap().iter().flat_map(|x| x.to_le_bytes()).collect(),
+        },
+    };
+    assert!(check_bundle(bundle("PY2023"), &policy).verified);
+
+    let verdict = check_bundle(bundle("PY2024"), &policy);
+    assert!(!verdict.verified && verdict.reason.starts_with("no policy entry allows PY2024"));
+}
+
+#[test]
+fn refuses_oversized_receipts() {
+    let receipt = vec![b'x'; MAX_RECEIPT_BYTES];
+    assert_eq!(read_receipt(receipt.as_slice(), Some(receipt.len())).unwrap().map(|x| x.len()), Some(MAX_RECEIPT_BYTES));
+    assert_eq!(read_receipt(receipt.as_slice(), Some(MAX_RECEIPT_BYTES + 1)).unwrap(), None);
+
+    // The cap holds without a declared length too
+    let receipt = vec![b'x'; MAX_RECEIPT_BYTES + 1];
+    assert_eq!(read_receipt(receipt.as_slice(), None).unwrap(), None);
+}
@@ -0,0 +1,40 @@
+use risc0_zkvm::guest::env;
+use risc0_zkvm::guest::env::log;
+risc0_zkvm::guest::entry!(main);
+use zero_raf_core::compiled::{tables_digest, CompiledTablesView};
+use zero_raf_core::dx_table::DxTableCommitment;
+use zero_raf_core::interactions::InteractionDefinitions;
+use zero_raf_core::population::{score_population, RosterMember};
+use zero_raf_methods_guest::RiscZeroSha;
+
+// Scores a whole roster and commits only the population aggregates and a Merkle root of the
+// member results, see `zero_raf_core::population`. Member scores, diagnoses and HCCs never
+// reach the journal.
+pub fn main() {
+
+    let _public_words: Vec<u32> = env::read();
+    let _public_tables = CompiledTablesView::new(&_public_words)
+        .expect("Public inputs should be compiled by the Host with a matching format version");
+    let _dx_commitment: DxTableCommitment = env::read();
+    let _interactions: InteractionDefinitions = env::read();
+
+    // Bind the journal to the exact tables the roster was scored with
+    let _tables_digest = tables_digest::<RiscZeroSha>(&_public_words);
+
+    log("Retrieved public inputs");
+
+    let _roster: Vec<RosterMember> = env::read();
+
+    log("Retrieved roster");
+
+    let journal = match score_population::<RiscZeroSha>(_public_tables, _tables_digest, &_interactions, &_dx_commitment, &_roster) {
+        Ok(journal) => journal,
+        Err(e) => panic!("Unable to score roster: {}", e),
+    };
+
+    log("Scored roster");
+
+    env::commit(&journal);
+
+    log("Committed journal");
+}
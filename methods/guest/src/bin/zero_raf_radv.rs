@@ -0,0 +1,44 @@
+use risc0_zkvm::guest::env;
+use risc0_zkvm::guest::env::log;
+risc0_zkvm::guest::entry!(main);
+use zero_raf_core::compiled::{tables_digest, CompiledTablesView};
+use zero_raf_core::dx_table::{DxLookup, DxTableCommitment};
+use zero_raf_core::interactions::InteractionDefinitions;
+use zero_raf_core::merkle::Hash;
+use zero_raf_core::radv::prove_hcc_presence;
+use zero_raf_core::PrivateRAFInput;
+use zero_raf_methods_guest::RiscZeroSha;
+
+// Proves whether a named HCC is present for a committed member, with the dx_to_cc row that
+// supports it, see `zero_raf_core::radv`. Other diagnoses never reach the journal.
+pub fn main() {
+
+    let _public_words: Vec<u32> = env::read();
+    let _public_tables = CompiledTablesView::new(&_public_words)
+        .expect("Public inputs should be compiled by the Host with a matching format version");
+    let _dx_commitment: DxTableCommitment = env::read();
+    let _interactions: InteractionDefinitions = env::read();
+    let _input_commitment: Hash = env::read();
+    let _hcc: String = env::read();
+    let _tables_digest = tables_digest::<RiscZeroSha>(&_public_words);
+
+    log("Retrieved public inputs");
+
+    let _salt: Hash = env::read();
+    let _private_input: PrivateRAFInput = env::read();
+    let _dx_lookups: Vec<DxLookup> = env::read();
+
+    log("Retrieved private input");
+
+    let journal = match prove_hcc_presence::<RiscZeroSha>(_public_tables, _tables_digest, &_interactions, &_dx_commitment,
+                                                          &_input_commitment, &_salt, &_private_input, &_dx_lookups, &_hcc) {
+        Ok(journal) => journal,
+        Err(e) => panic!("Unable to prove HCC presence: {}", e),
+    };
+
+    log("Checked HCC presence");
+
+    env::commit(&journal);
+
+    log("Committed journal");
+}
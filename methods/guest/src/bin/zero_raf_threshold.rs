@@ -0,0 +1,45 @@
+use risc0_zkvm::guest::env;
+use risc0_zkvm::guest::env::log;
+risc0_zkvm::guest::entry!(main);
+use zero_raf_core::compiled::{tables_digest, CompiledTablesView};
+use zero_raf_core::dx_table::{DxLookup, DxTableCommitment};
+use zero_raf_core::interactions::InteractionDefinitions;
+use zero_raf_core::merkle::Hash;
+use zero_raf_core::threshold::{evaluate_threshold, ScoreCondition};
+use zero_raf_core::PrivateRAFInput;
+use zero_raf_methods_guest::RiscZeroSha;
+
+// Checks the score of a beneficiary against a public condition and commits only whether it
+// is met for the committed member, see `zero_raf_core::threshold`. The score itself never
+// reaches the journal.
+pub fn main() {
+
+    let _public_words: Vec<u32> = env::read();
+    let _public_tables = CompiledTablesView::new(&_public_words)
+        .expect("Public inputs should be compiled by the Host with a matching format version");
+    let _dx_commitment: DxTableCommitment = env::read();
+    let _interactions: InteractionDefinitions = env::read();
+    let _condition: ScoreCondition = env::read();
+    let _input_commitment: Hash = env::read();
+    let _tables_digest = tables_digest::<RiscZeroSha>(&_public_words);
+
+    log("Retrieved public inputs");
+
+    let _salt: Hash = env::read();
+    let _private_input: PrivateRAFInput = env::read();
+    let _dx_lookups: Vec<DxLookup> = env::read();
+
+    log("Retrieved private input");
+
+    let journal = match evaluate_threshold::<RiscZeroSha>(_public_tables, _tables_digest, &_interactions, &_dx_commitment,
+                                                          &_input_commitment, &_salt, &_private_input, &_dx_lookups, _condition) {
+        Ok(journal) => journal,
+        Err(e) => panic!("Unable to evaluate threshold: {}", e),
+    };
+
+    log("Evaluated threshold");
+
+    env::commit(&journal);
+
+    log("Committed journal");
+}
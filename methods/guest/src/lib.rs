@@ -0,0 +1,40 @@
+use risc0_zkvm::guest::env;
+use risc0_zkvm::guest::env::log;
+use risc0_zkvm::sha::{Impl, Sha256};
+use std::io::Write;
+use zero_raf_core::bench::phase_marker;
+use zero_raf_core::merkle::{Hash, MerkleHasher};
+
+// Helpers shared by the Guest programs in src/bin.
+
+// Merkle hashing for the dx_to_cc proofs using the zkVM SHA-256 accelerator
+pub struct RiscZeroSha;
+
+impl MerkleHasher for RiscZeroSha {
+    fn sha256(data: &[u8]) -> Hash {
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(Impl::hash_bytes(data).as_bytes());
+        hash
+    }
+}
+
+// Logs the end of each phase of the scoring Guest. The cycle count markers read by the
+// benchmark harness cost cycles of their own, so they are only written when the Host asks.
+pub struct PhaseLog {
+    markers: bool,
+}
+
+impl PhaseLog {
+    // Reads whether to write phase markers, the first input of the scoring Guest
+    pub fn read() -> Self {
+        PhaseLog { markers: env::read() }
+    }
+
+    pub fn phase(&self, message: &str) {
+        log(message);
+        if self.markers {
+            let marker = phase_marker(message, env::get_cycle_count() as u64);
+            env::stdout().write_all(marker.as_bytes()).unwrap();
+        }
+    }
+}